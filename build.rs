@@ -15,60 +15,258 @@
  */
 
 use csv::{Reader, ReaderBuilder};
+#[cfg(feature = "download")]
 use reqwest::blocking::Client;
-use rusqlite::{params, Connection, Error, NO_PARAMS};
+use rusqlite::{params, Connection, NO_PARAMS};
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 use std::env::var_os;
-use std::fs::File;
+use std::fs::{copy, create_dir_all, read, remove_file, File};
 use std::io::Write;
 use std::io::{BufReader, Read};
+use std::iter::once;
 use std::ops::RangeInclusive;
 use std::option::Option::Some;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use zip::write::FileOptions;
 use zip::ZipWriter;
 
-const UCD_URL: &str = "http://ftp.unicode.org/Public/13.0.0/ucd";
+#[cfg(feature = "download")]
+const UCD_BASE_URL: &str = "https://www.unicode.org/Public";
+const DEFAULT_UNICODE_VERSION: &str = "13.0.0";
+// Newest Unicode version whose DerivedAge.txt is vendored, so that code points
+// assigned after the version of the database can still be recognized.
+const NEWEST_UNICODE_VERSION: &str = "14.0.0";
+const UNICODE_VERSION_ENV_VAR: &str = "CHR_UNICODE_VERSION";
+const UCD_DIRECTORY_ENV_VAR: &str = "CHR_UCD_DIR";
+const VENDORED_UCD_DIRECTORY_NAME: &str = "ucd";
+const ALLOW_UNPINNED_FILES_ENV_VAR: &str = "CHR_ALLOW_UNPINNED_UCD_FILES";
 
+const BIDI_BRACKETS_FILE_NAME: &str = "BidiBrackets.txt";
+const BIDI_MIRRORING_FILE_NAME: &str = "BidiMirroring.txt";
 const BLOCKS_FILE_NAME: &str = "Blocks.txt";
+const CASE_FOLDING_FILE_NAME: &str = "CaseFolding.txt";
+const COMPOSITION_EXCLUSIONS_FILE_NAME: &str = "CompositionExclusions.txt";
 const DERIVED_AGE_FILE_NAME: &str = "DerivedAge.txt";
+const DERIVED_CORE_PROPERTIES_FILE_NAME: &str = "DerivedCoreProperties.txt";
+const NAME_ALIASES_FILE_NAME: &str = "NameAliases.txt";
+const NAMES_LIST_FILE_NAME: &str = "NamesList.txt";
+const PROP_LIST_FILE_NAME: &str = "PropList.txt";
+const SPECIAL_CASING_FILE_NAME: &str = "SpecialCasing.txt";
 const UNICODE_DATA_FILE_NAME: &str = "UnicodeData.txt";
+const UNIHAN_NUMERIC_VALUES_FILE_NAME: &str = "Unihan_NumericValues.txt";
+#[cfg(feature = "download")]
+const UNIHAN_ZIP_FILE_NAME: &str = "Unihan.zip";
 const DATABASE_FILE_NAME: &str = "chr.db";
 const ZIP_FILE_NAME: &str = "chr.db.zip";
 
+const UCD_FILE_NAMES: [&str; 13] = [
+    BIDI_BRACKETS_FILE_NAME,
+    BIDI_MIRRORING_FILE_NAME,
+    BLOCKS_FILE_NAME,
+    CASE_FOLDING_FILE_NAME,
+    COMPOSITION_EXCLUSIONS_FILE_NAME,
+    DERIVED_AGE_FILE_NAME,
+    DERIVED_CORE_PROPERTIES_FILE_NAME,
+    NAME_ALIASES_FILE_NAME,
+    NAMES_LIST_FILE_NAME,
+    PROP_LIST_FILE_NAME,
+    SPECIAL_CASING_FILE_NAME,
+    UNICODE_DATA_FILE_NAME,
+    UNIHAN_NUMERIC_VALUES_FILE_NAME,
+];
+
+// Case foldings of status T in CaseFolding.txt apply to the Turkic languages,
+// which SpecialCasing.txt names by their language codes.
+const TURKIC_LANGUAGES: [&str; 2] = ["tr", "az"];
+
+const HANGUL_SYLLABLE_BASE: u32 = 0xAC00;
+const HANGUL_LEADING_JAMO_BASE: u32 = 0x1100;
+const HANGUL_VOWEL_JAMO_BASE: u32 = 0x1161;
+const HANGUL_TRAILING_JAMO_BASE: u32 = 0x11A7;
+const HANGUL_VOWEL_COUNT: usize = 21;
+const HANGUL_TRAILING_COUNT: usize = 28;
+
+// Short names of the conjoining jamo from Jamo.txt, used to derive Hangul syllable names.
+const HANGUL_LEADING_JAMO_NAMES: [&str; 19] = [
+    "G", "GG", "N", "D", "DD", "R", "M", "B", "BB", "S", "SS", "", "J", "JJ", "C", "K", "T", "P",
+    "H",
+];
+const HANGUL_VOWEL_JAMO_NAMES: [&str; HANGUL_VOWEL_COUNT] = [
+    "A", "AE", "YA", "YAE", "EO", "E", "YEO", "YE", "O", "WA", "WAE", "OE", "YO", "U", "WEO", "WE",
+    "WI", "YU", "EU", "YI", "I",
+];
+const HANGUL_TRAILING_JAMO_NAMES: [&str; HANGUL_TRAILING_COUNT] = [
+    "", "G", "GG", "GS", "N", "NJ", "NH", "D", "L", "LG", "LM", "LB", "LS", "LT", "LP", "LH", "M",
+    "B", "BS", "S", "SS", "NG", "J", "C", "K", "T", "P", "H",
+];
+
+// SHA-256 checksums of the UCD files, pinned per Unicode version.
+// A file without an entry here aborts the build unless unpinned files are allowed explicitly.
+const UCD_FILE_CHECKSUMS: [(&str, &str, &str); 4] = [
+    (
+        "13.0.0",
+        CASE_FOLDING_FILE_NAME,
+        "99d231d7c91688bbe8ca8ccebcc2f46b5b222f844babe4827295bae11e2abe5f",
+    ),
+    (
+        "13.0.0",
+        NAME_ALIASES_FILE_NAME,
+        "985128e8c8b2540233331d9b27d897d2c23bd5bcf1d05da960befce6efc59542",
+    ),
+    (
+        "13.0.0",
+        UNICODE_DATA_FILE_NAME,
+        "bdbffbbfc8ad4d3a6d01b5891510458f3d36f7170422af4ea2bed3211a73e8bb",
+    ),
+    (
+        "14.0.0",
+        DERIVED_AGE_FILE_NAME,
+        "1cdd9720984cd57c62262f67529fd2b35a1dac522c8ddd43e919727ef3541376",
+    ),
+];
+
 fn main() {
     let out_dir = var_os("OUT_DIR").unwrap();
     let target_directory_path = Path::new(&out_dir);
 
-    download_files(target_directory_path);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let unicode_version = select_unicode_version();
+    let ucd_directory_path = locate_ucd_files(target_directory_path, &unicode_version);
 
-    let mut unicode_char_data_map = process_unicode_data_file(target_directory_path);
+    verify_checksums(&ucd_directory_path, &unicode_version);
 
-    process_blocks_file(target_directory_path, &mut unicode_char_data_map);
-    process_derived_age_file(target_directory_path, &mut unicode_char_data_map);
+    let mut unicode_char_data_map = process_unicode_data_file(&ucd_directory_path);
 
-    save_to_database(target_directory_path, unicode_char_data_map);
+    process_blocks_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_derived_age_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_name_aliases_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_names_list_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_composition_exclusions_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_special_casing_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_case_folding_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_case_properties(&ucd_directory_path, &mut unicode_char_data_map);
+    process_bidi_mirroring_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_bidi_brackets_file(&ucd_directory_path, &mut unicode_char_data_map);
+    process_unihan_numeric_values_file(&ucd_directory_path, &mut unicode_char_data_map);
+
+    let newer_ages = process_newest_derived_age_file(&unicode_version);
+
+    save_to_database(
+        target_directory_path,
+        unicode_char_data_map,
+        newer_ages,
+        &unicode_version,
+    );
     compress_database(target_directory_path);
+    export_database_checksum(target_directory_path);
+}
+
+fn select_unicode_version() -> String {
+    println!("cargo:rerun-if-env-changed={}", UNICODE_VERSION_ENV_VAR);
+
+    let unicode_version = var_os(UNICODE_VERSION_ENV_VAR)
+        .map(|version| version.to_string_lossy().trim().to_string())
+        .unwrap_or_else(|| DEFAULT_UNICODE_VERSION.to_string());
+
+    let is_valid_version = unicode_version.split('.').count() == 3
+        && unicode_version
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+
+    if !is_valid_version {
+        panic!(
+            "{} must be a Unicode version such as {}, but is '{}'",
+            UNICODE_VERSION_ENV_VAR, DEFAULT_UNICODE_VERSION, unicode_version
+        );
+    }
+
+    println!("cargo:rustc-env=CHR_UNICODE_VERSION={}", unicode_version);
+
+    unicode_version
 }
 
-fn download_files(target_directory_path: &Path) {
-    let ucd_base_url = Path::new(UCD_URL);
-    let file_urls = vec![
-        ucd_base_url.join(BLOCKS_FILE_NAME),
-        ucd_base_url.join(DERIVED_AGE_FILE_NAME),
-        ucd_base_url.join(UNICODE_DATA_FILE_NAME),
-    ];
+fn locate_ucd_files(target_directory_path: &Path, unicode_version: &str) -> PathBuf {
+    println!("cargo:rerun-if-env-changed={}", UCD_DIRECTORY_ENV_VAR);
+
+    let vendored_ucd_directory_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(VENDORED_UCD_DIRECTORY_NAME)
+        .join(unicode_version);
+    let source_directory_paths = var_os(UCD_DIRECTORY_ENV_VAR)
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(once(vendored_ucd_directory_path))
+        .filter(|directory_path| directory_path.is_dir())
+        .collect::<Vec<_>>();
+
+    // Each file is taken from the first directory containing it, so the UCD files
+    // used for building the database are collected in a single directory.
+    let ucd_directory_path = target_directory_path
+        .join(VENDORED_UCD_DIRECTORY_NAME)
+        .join(unicode_version);
+    let mut missing_file_names = vec![];
+
+    create_dir_all(&ucd_directory_path).expect("UCD directory could not be created");
+
+    for directory_path in source_directory_paths.iter() {
+        println!("cargo:rerun-if-changed={}", directory_path.display());
+    }
+
+    for file_name in UCD_FILE_NAMES.iter() {
+        let source_file_path = source_directory_paths
+            .iter()
+            .map(|directory_path| directory_path.join(file_name))
+            .find(|file_path| file_path.is_file());
+
+        match source_file_path {
+            Some(source_file_path) => {
+                copy(&source_file_path, ucd_directory_path.join(file_name)).unwrap_or_else(|_| {
+                    panic!("File {} could not be copied", source_file_path.display())
+                });
+            }
+            None => missing_file_names.push(*file_name),
+        }
+    }
+
+    if !missing_file_names.is_empty() {
+        download_files(&ucd_directory_path, unicode_version, &missing_file_names);
+    }
+
+    ucd_directory_path
+}
+
+#[cfg(not(feature = "download"))]
+fn download_files(_target_directory_path: &Path, unicode_version: &str, file_names: &[&str]) {
+    panic!(
+        "UCD files {} of Unicode {} could not be found. Either set {} to a directory \
+         containing them, put them into {}/{} or enable the 'download' feature",
+        file_names.join(", "),
+        unicode_version,
+        UCD_DIRECTORY_ENV_VAR,
+        VENDORED_UCD_DIRECTORY_NAME,
+        unicode_version
+    );
+}
+
+#[cfg(feature = "download")]
+fn download_files(target_directory_path: &Path, unicode_version: &str, file_names: &[&str]) {
+    let ucd_url = format!("{}/{}/ucd", UCD_BASE_URL, unicode_version);
     let client = Client::new();
 
-    for file_url in file_urls {
-        let file_path = target_directory_path.join(file_url.file_name().unwrap());
+    for file_name in file_names {
+        let file_path = target_directory_path.join(file_name);
 
-        if !file_path.exists() {
-            let mut file_data = client
-                .get(file_url.to_str().unwrap())
-                .send()
-                .expect("File download failed");
+        if file_path.exists() {
+            continue;
+        }
+
+        if *file_name == UNIHAN_NUMERIC_VALUES_FILE_NAME {
+            download_unihan_file(&client, &ucd_url, &file_path);
+        } else {
+            let file_url = format!("{}/{}", ucd_url, file_name);
+            let mut file_data = client.get(&file_url).send().expect("File download failed");
             let mut file =
                 File::create(file_path).expect("New file could not be created at the given path");
 
@@ -79,8 +277,76 @@ fn download_files(target_directory_path: &Path) {
     }
 }
 
-fn process_unicode_data_file(target_directory_path: &Path) -> BTreeMap<u32, UnicodeCharData> {
-    let mut csv_file_reader = open_csv_file_reader(target_directory_path, UNICODE_DATA_FILE_NAME);
+// The Unihan database is only published as a zip archive,
+// from which the numeric values are extracted.
+#[cfg(feature = "download")]
+fn download_unihan_file(client: &Client, ucd_url: &str, file_path: &Path) {
+    let zip_file_url = format!("{}/{}", ucd_url, UNIHAN_ZIP_FILE_NAME);
+    let mut zip_file_data = vec![];
+
+    client
+        .get(&zip_file_url)
+        .send()
+        .expect("File download failed")
+        .copy_to(&mut zip_file_data)
+        .expect("Downloaded file data could not be read");
+
+    let mut zip_archive = zip::ZipArchive::new(std::io::Cursor::new(zip_file_data))
+        .expect("Unihan zip archive could not be opened");
+    let mut zip_file = zip_archive
+        .by_name(UNIHAN_NUMERIC_VALUES_FILE_NAME)
+        .expect("Unihan zip archive does not contain the numeric values");
+    let mut file =
+        File::create(file_path).expect("New file could not be created at the given path");
+
+    std::io::copy(&mut zip_file, &mut file)
+        .expect("Unzipped file data could not be written to disk");
+}
+
+fn verify_checksums(ucd_directory_path: &Path, unicode_version: &str) {
+    for file_name in UCD_FILE_NAMES.iter() {
+        verify_checksum(ucd_directory_path, file_name, unicode_version);
+    }
+}
+
+fn verify_checksum(ucd_directory_path: &Path, file_name: &str, unicode_version: &str) {
+    println!(
+        "cargo:rerun-if-env-changed={}",
+        ALLOW_UNPINNED_FILES_ENV_VAR
+    );
+
+    let are_unpinned_files_allowed = var_os(ALLOW_UNPINNED_FILES_ENV_VAR).is_some();
+    let file_path = ucd_directory_path.join(file_name);
+    let file_content = read(&file_path)
+        .unwrap_or_else(|_| panic!("File {} could not be read", file_path.display()));
+    let checksum = format!("{:x}", Sha256::digest(&file_content));
+    let pinned_checksum = UCD_FILE_CHECKSUMS
+        .iter()
+        .find(|(version, name, _)| *version == unicode_version && *name == file_name)
+        .map(|(_, _, checksum)| *checksum);
+
+    match pinned_checksum {
+        Some(pinned_checksum) if pinned_checksum != checksum => panic!(
+            "Checksum of {} does not match, expected {} but found {}",
+            file_path.display(),
+            pinned_checksum,
+            checksum
+        ),
+        Some(_) => {}
+        None if are_unpinned_files_allowed => println!(
+            "cargo:warning=No checksum pinned for {} of Unicode {}, file is used unverified",
+            file_name, unicode_version
+        ),
+        None => panic!(
+            "No checksum pinned for {} of Unicode {}. Pin it in UCD_FILE_CHECKSUMS \
+             or set {} to use the file unverified",
+            file_name, unicode_version, ALLOW_UNPINNED_FILES_ENV_VAR
+        ),
+    }
+}
+
+fn process_unicode_data_file(ucd_directory_path: &Path) -> BTreeMap<u32, UnicodeCharData> {
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, UNICODE_DATA_FILE_NAME);
     let mut csv_row_iterator = csv_file_reader.deserialize::<UnicodeDataFileRow>();
     let mut unicode_char_data_map = BTreeMap::<u32, UnicodeCharData>::new();
 
@@ -89,14 +355,27 @@ fn process_unicode_data_file(target_directory_path: &Path) -> BTreeMap<u32, Unic
         let codepoint = to_decimal_number(&unicode_data_file_row.hexcode);
 
         if unicode_data_file_row.name.ends_with("First>") {
-            let name = &unicode_data_file_row.name.split(',').next().unwrap()[1..];
+            let range_name = &unicode_data_file_row.name.split(',').next().unwrap()[1..];
             let next_row = csv_row_iterator.next().unwrap().unwrap();
             let last_codepoint = to_decimal_number(&next_row.hexcode);
 
             for point in codepoint..=last_codepoint {
-                let unicode_char_data = UnicodeCharData::from(&unicode_data_file_row, point, name);
+                let name = derive_name(range_name, point);
+                let mut unicode_char_data =
+                    UnicodeCharData::from(&unicode_data_file_row, point, &name);
+
+                if range_name == "Hangul Syllable" {
+                    unicode_char_data.decomposition =
+                        Some(derive_hangul_syllable_decomposition(point));
+                }
+
                 unicode_char_data_map.insert(point, unicode_char_data);
             }
+        } else if unicode_data_file_row.name == "<control>" {
+            let label = format!("<control-{:04X}>", codepoint);
+            let unicode_char_data =
+                UnicodeCharData::from(&unicode_data_file_row, codepoint, &label);
+            unicode_char_data_map.insert(codepoint, unicode_char_data);
         } else {
             let unicode_char_data = UnicodeCharData::from(
                 &unicode_data_file_row,
@@ -110,11 +389,71 @@ fn process_unicode_data_file(target_directory_path: &Path) -> BTreeMap<u32, Unic
     unicode_char_data_map
 }
 
+// Derives the name of a code point within a range of UnicodeData.txt according to the
+// name derivation rules of section 4.8 of the Unicode Standard. Code points without
+// a name, such as surrogates and private use code points, are given a code point label.
+fn derive_name(range_name: &str, codepoint: u32) -> String {
+    if range_name.starts_with("CJK Ideograph") {
+        format!("CJK UNIFIED IDEOGRAPH-{:04X}", codepoint)
+    } else if range_name.starts_with("Tangut Ideograph") {
+        format!("TANGUT IDEOGRAPH-{:04X}", codepoint)
+    } else if range_name == "Hangul Syllable" {
+        derive_hangul_syllable_name(codepoint)
+    } else if range_name.ends_with("Surrogate") {
+        format!("<surrogate-{:04X}>", codepoint)
+    } else if range_name.ends_with("Private Use") {
+        format!("<private-use-{:04X}>", codepoint)
+    } else {
+        panic!("Name of range '{}' cannot be derived", range_name)
+    }
+}
+
+fn derive_hangul_syllable_name(codepoint: u32) -> String {
+    let syllable_index = (codepoint - HANGUL_SYLLABLE_BASE) as usize;
+    let leading_index = syllable_index / (HANGUL_VOWEL_COUNT * HANGUL_TRAILING_COUNT);
+    let vowel_index =
+        (syllable_index % (HANGUL_VOWEL_COUNT * HANGUL_TRAILING_COUNT)) / HANGUL_TRAILING_COUNT;
+    let trailing_index = syllable_index % HANGUL_TRAILING_COUNT;
+
+    format!(
+        "HANGUL SYLLABLE {}{}{}",
+        HANGUL_LEADING_JAMO_NAMES[leading_index],
+        HANGUL_VOWEL_JAMO_NAMES[vowel_index],
+        HANGUL_TRAILING_JAMO_NAMES[trailing_index]
+    )
+}
+
+// Hangul syllables are decomposed algorithmically as described in section 3.12 of the
+// Unicode Standard, either into a leading and a vowel jamo or into an LV syllable
+// and a trailing jamo, so that the full decomposition is reached recursively.
+fn derive_hangul_syllable_decomposition(codepoint: u32) -> String {
+    let syllable_index = codepoint - HANGUL_SYLLABLE_BASE;
+    let trailing_index = syllable_index % HANGUL_TRAILING_COUNT as u32;
+
+    if trailing_index == 0 {
+        let leading_index = syllable_index / (HANGUL_VOWEL_COUNT * HANGUL_TRAILING_COUNT) as u32;
+        let vowel_index = (syllable_index % (HANGUL_VOWEL_COUNT * HANGUL_TRAILING_COUNT) as u32)
+            / HANGUL_TRAILING_COUNT as u32;
+
+        format!(
+            "{:04X} {:04X}",
+            HANGUL_LEADING_JAMO_BASE + leading_index,
+            HANGUL_VOWEL_JAMO_BASE + vowel_index
+        )
+    } else {
+        format!(
+            "{:04X} {:04X}",
+            codepoint - trailing_index,
+            HANGUL_TRAILING_JAMO_BASE + trailing_index
+        )
+    }
+}
+
 fn process_blocks_file(
-    target_directory_path: &Path,
+    ucd_directory_path: &Path,
     unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
 ) {
-    let mut csv_file_reader = open_csv_file_reader(target_directory_path, BLOCKS_FILE_NAME);
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, BLOCKS_FILE_NAME);
 
     for result in csv_file_reader.records() {
         let row = result.expect("CSV row could not be unwrapped");
@@ -136,10 +475,10 @@ fn process_blocks_file(
 }
 
 fn process_derived_age_file(
-    target_directory_path: &Path,
+    ucd_directory_path: &Path,
     unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
 ) {
-    let mut csv_file_reader = open_csv_file_reader(target_directory_path, DERIVED_AGE_FILE_NAME);
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, DERIVED_AGE_FILE_NAME);
 
     for result in csv_file_reader.records() {
         let row = result.expect("CSV row could not be unwrapped");
@@ -160,12 +499,458 @@ fn process_derived_age_file(
     }
 }
 
+// Collects the code point ranges of the vendored DerivedAge.txt of the newest known Unicode version
+// that were assigned after the version of the database. They are looked up for code points
+// that are missing in the database.
+fn process_newest_derived_age_file(unicode_version: &str) -> Vec<(u32, u32, String)> {
+    let ucd_directory_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(VENDORED_UCD_DIRECTORY_NAME)
+        .join(NEWEST_UNICODE_VERSION);
+
+    println!("cargo:rerun-if-changed={}", ucd_directory_path.display());
+
+    verify_checksum(
+        &ucd_directory_path,
+        DERIVED_AGE_FILE_NAME,
+        NEWEST_UNICODE_VERSION,
+    );
+
+    let database_version = parse_version(unicode_version);
+    let mut csv_file_reader = open_csv_file_reader(&ucd_directory_path, DERIVED_AGE_FILE_NAME);
+    let mut newer_ages = vec![];
+
+    for result in csv_file_reader.records() {
+        let row = result.expect("CSV row could not be unwrapped");
+
+        if row.len() == 2 {
+            let codepoints = row.get(0).unwrap().trim();
+            let age = row.get(1).unwrap().split('#').next().unwrap().trim();
+
+            if !codepoints.starts_with('#') && parse_version(age) > database_version {
+                let mut range = codepoints.split("..").map(to_decimal_number);
+                let first_codepoint = range.next().unwrap();
+                let last_codepoint = range.next().unwrap_or(first_codepoint);
+
+                newer_ages.push((first_codepoint, last_codepoint, age.to_string()));
+            }
+        }
+    }
+
+    newer_ages
+}
+
+fn parse_version(version: &str) -> (u32, u32) {
+    let mut parts = version
+        .split('.')
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+}
+
+// Marks the characters whose canonical decompositions are not recomposed by NFC and NFKC,
+// which is the derived property Full_Composition_Exclusion. Besides the characters listed
+// in CompositionExclusions.txt, these are singletons and non-starter decompositions.
+fn process_composition_exclusions_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let mut csv_file_reader =
+        open_csv_file_reader(ucd_directory_path, COMPOSITION_EXCLUSIONS_FILE_NAME);
+
+    for result in csv_file_reader.records() {
+        let row = result.expect("CSV row could not be unwrapped");
+        let codepoints = row.get(0).unwrap().split('#').next().unwrap().trim();
+
+        if !codepoints.is_empty() {
+            for codepoint in codepoint_range(codepoints) {
+                if let Some(char_data) = unicode_char_data_map.get_mut(&codepoint) {
+                    char_data.is_composition_excluded = true;
+                }
+            }
+        }
+    }
+
+    let combining_classes = unicode_char_data_map
+        .iter()
+        .map(|(codepoint, char_data)| (*codepoint, char_data.combining_class))
+        .collect::<BTreeMap<_, _>>();
+
+    for char_data in unicode_char_data_map.values_mut() {
+        let canonical_mapping = match &char_data.decomposition {
+            Some(mapping) if !mapping.starts_with('<') => mapping
+                .split_whitespace()
+                .map(to_decimal_number)
+                .collect::<Vec<_>>(),
+            _ => continue,
+        };
+        let is_singleton = canonical_mapping.len() == 1;
+        let is_non_starter_decomposition = char_data.combining_class != 0
+            || combining_classes.get(&canonical_mapping[0]).copied() != Some(0);
+
+        if is_singleton || is_non_starter_decomposition {
+            char_data.is_composition_excluded = true;
+        }
+    }
+}
+
+// The full case mappings default to the simple ones of UnicodeData.txt and are replaced
+// by the unconditional entries of SpecialCasing.txt. Entries with a condition list only
+// apply to certain languages or contexts, so they are kept apart for case conversion.
+fn process_special_casing_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, SPECIAL_CASING_FILE_NAME);
+
+    for result in csv_file_reader.records() {
+        let row = result.expect("CSV row could not be unwrapped");
+        let codepoint = row.get(0).unwrap().trim();
+
+        if row.len() < 5 || codepoint.starts_with('#') {
+            continue;
+        }
+
+        let char_data = match unicode_char_data_map.get_mut(&to_decimal_number(codepoint)) {
+            Some(char_data) => char_data,
+            None => continue,
+        };
+        let mappings = [
+            ("lowercase", row.get(1).unwrap().trim()),
+            ("titlecase", row.get(2).unwrap().trim()),
+            ("uppercase", row.get(3).unwrap().trim()),
+        ];
+        let conditions = row.get(4).unwrap().trim();
+
+        if conditions.starts_with('#') {
+            char_data.full_lowercase = Some(mappings[0].1.to_string());
+            char_data.full_titlecase = Some(mappings[1].1.to_string());
+            char_data.full_uppercase = Some(mappings[2].1.to_string());
+            continue;
+        }
+
+        // A condition list consists of an optional language code, which is written
+        // in lowercase, followed by an optional context such as Final_Sigma.
+        let (language, context) = match conditions.split_once(' ') {
+            Some((language, context)) => (Some(language), Some(context)),
+            None if conditions.chars().all(|c| c.is_ascii_lowercase()) => (Some(conditions), None),
+            None => (None, Some(conditions)),
+        };
+
+        for (mapping_type, mapping) in mappings.iter() {
+            char_data.conditional_case_mappings.push((
+                *mapping_type,
+                mapping.to_string(),
+                language.map(String::from),
+                context.map(String::from),
+            ));
+        }
+    }
+
+    for char_data in unicode_char_data_map.values_mut() {
+        let own_mapping = format!("{:04X}", char_data.codepoint);
+
+        for full_mapping in [
+            &mut char_data.full_lowercase,
+            &mut char_data.full_titlecase,
+            &mut char_data.full_uppercase,
+        ] {
+            if full_mapping.as_deref() == Some(own_mapping.as_str()) {
+                *full_mapping = None;
+            }
+        }
+    }
+}
+
+// Common case foldings of status C are both simple and full ones, whereas status S
+// is only simple and status F only full. Status T is the Turkic tailoring of the
+// foldings of the dotted and dotless capital I.
+fn process_case_folding_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, CASE_FOLDING_FILE_NAME);
+
+    for result in csv_file_reader.records() {
+        let row = result.expect("CSV row could not be unwrapped");
+        let codepoint = row.get(0).unwrap().trim();
+
+        if row.len() < 3 || codepoint.starts_with('#') {
+            continue;
+        }
+
+        let status = row.get(1).unwrap().trim();
+        let mapping = row.get(2).unwrap().trim().to_string();
+
+        if let Some(char_data) = unicode_char_data_map.get_mut(&to_decimal_number(codepoint)) {
+            match status {
+                "C" => {
+                    char_data.simple_case_folding = Some(mapping.clone());
+                    char_data.full_case_folding = Some(mapping);
+                }
+                "S" => char_data.simple_case_folding = Some(mapping),
+                "F" => char_data.full_case_folding = Some(mapping),
+                "T" => {
+                    for language in TURKIC_LANGUAGES.iter() {
+                        char_data.conditional_case_mappings.push((
+                            "case_folding",
+                            mapping.clone(),
+                            Some(language.to_string()),
+                            None,
+                        ));
+                    }
+                }
+                _ => panic!("Unknown case folding status '{}'", status),
+            }
+        }
+    }
+
+    for char_data in unicode_char_data_map.values_mut() {
+        if char_data.full_case_folding.is_none() {
+            char_data.full_case_folding = char_data.simple_case_folding.clone();
+        }
+    }
+}
+
+// The contexts of conditional case mappings are defined in terms of the properties
+// Cased and Case_Ignorable from DerivedCoreProperties.txt and Soft_Dotted from PropList.txt.
+fn process_case_properties(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    for file_name in [DERIVED_CORE_PROPERTIES_FILE_NAME, PROP_LIST_FILE_NAME].iter() {
+        let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, file_name);
+
+        for result in csv_file_reader.records() {
+            let row = result.expect("CSV row could not be unwrapped");
+
+            if row.len() == 2 {
+                let codepoints = row.get(0).unwrap().trim();
+                let property = row.get(1).unwrap().split('#').next().unwrap().trim();
+
+                if codepoints.starts_with('#') {
+                    continue;
+                }
+
+                for codepoint in codepoint_range(codepoints) {
+                    if let Some(char_data) = unicode_char_data_map.get_mut(&codepoint) {
+                        match property {
+                            "Cased" => char_data.is_cased = true,
+                            "Case_Ignorable" => char_data.is_case_ignorable = true,
+                            "Soft_Dotted" => char_data.is_soft_dotted = true,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Bidi_Mirroring_Glyph is only given for mirrored characters whose mirror image
+// is another character, so some characters are mirrored without a mirroring glyph.
+fn process_bidi_mirroring_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, BIDI_MIRRORING_FILE_NAME);
+
+    for result in csv_file_reader.records() {
+        let row = result.expect("CSV row could not be unwrapped");
+        let codepoint = row.get(0).unwrap().trim();
+
+        if row.len() < 2 || codepoint.starts_with('#') {
+            continue;
+        }
+
+        let mirroring_glyph = row.get(1).unwrap().split('#').next().unwrap().trim();
+
+        if let Some(char_data) = unicode_char_data_map.get_mut(&to_decimal_number(codepoint)) {
+            char_data.bidi_mirroring_glyph = Some(to_decimal_number(mirroring_glyph));
+        }
+    }
+}
+
+// Paired brackets are typed as o for opening and c for closing brackets.
+fn process_bidi_brackets_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, BIDI_BRACKETS_FILE_NAME);
+
+    for result in csv_file_reader.records() {
+        let row = result.expect("CSV row could not be unwrapped");
+        let codepoint = row.get(0).unwrap().trim();
+
+        if row.len() < 3 || codepoint.starts_with('#') {
+            continue;
+        }
+
+        let paired_bracket = row.get(1).unwrap().trim();
+        let paired_bracket_type = row.get(2).unwrap().split('#').next().unwrap().trim();
+
+        if let Some(char_data) = unicode_char_data_map.get_mut(&to_decimal_number(codepoint)) {
+            char_data.bidi_paired_bracket = Some(to_decimal_number(paired_bracket));
+            char_data.bidi_paired_bracket_type = Some(paired_bracket_type.to_string());
+        }
+    }
+}
+
+// Unihan_NumericValues.txt is tab-separated. Its numeric values of CJK ideographs,
+// given by the fields kPrimaryNumeric, kAccountingNumeric and kOtherNumeric,
+// are all integers of the numeric type Nu. An ideograph with several of them
+// keeps the first one in this order.
+fn process_unihan_numeric_values_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let file_path = ucd_directory_path.join(UNIHAN_NUMERIC_VALUES_FILE_NAME);
+    let file_content = read(&file_path)
+        .unwrap_or_else(|_| panic!("File {} could not be read", file_path.display()));
+    let mut numeric_values = BTreeMap::<u32, Vec<(usize, String)>>::new();
+
+    for line in String::from_utf8_lossy(&file_content).lines() {
+        let fields = line.split('\t').collect::<Vec<_>>();
+
+        if let [codepoint, field, numeric_value] = fields[..] {
+            let precedence = ["kPrimaryNumeric", "kAccountingNumeric", "kOtherNumeric"]
+                .iter()
+                .position(|name| *name == field);
+
+            if let (Some(hexcode), Some(precedence)) = (codepoint.strip_prefix("U+"), precedence) {
+                numeric_values
+                    .entry(to_decimal_number(hexcode))
+                    .or_default()
+                    .push((precedence, numeric_value.trim().to_string()));
+            }
+        }
+    }
+
+    for (codepoint, mut values) in numeric_values {
+        if let Some(char_data) = unicode_char_data_map.get_mut(&codepoint) {
+            values.sort();
+            char_data.numeric_type = Some("Nu");
+            char_data.numeric_value = Some(values.swap_remove(0).1);
+        }
+    }
+}
+
+fn process_name_aliases_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let mut csv_file_reader = open_csv_file_reader(ucd_directory_path, NAME_ALIASES_FILE_NAME);
+
+    for result in csv_file_reader.records() {
+        let row = result.expect("CSV row could not be unwrapped");
+
+        if row.len() == 3 {
+            let codepoint = row.get(0).unwrap().trim();
+            let alias = row.get(1).unwrap().trim();
+            let alias_type = row.get(2).unwrap().trim();
+
+            if !codepoint.starts_with('#') {
+                if let Some(char_data) =
+                    unicode_char_data_map.get_mut(&to_decimal_number(codepoint))
+                {
+                    char_data
+                        .aliases
+                        .push((alias.to_string(), alias_type.to_string()));
+                }
+            }
+        }
+    }
+}
+
+// NamesList.txt is not a semicolon-separated file like the others. Character entries
+// start with their code point and are followed by annotation lines indented by a tab,
+// while lines starting with @ are headers whose annotations belong to no character.
+fn process_names_list_file(
+    ucd_directory_path: &Path,
+    unicode_char_data_map: &mut BTreeMap<u32, UnicodeCharData>,
+) {
+    let file_path = ucd_directory_path.join(NAMES_LIST_FILE_NAME);
+    let file_content = read(&file_path)
+        .unwrap_or_else(|_| panic!("File {} could not be read", file_path.display()));
+    let mut current_codepoint = None;
+
+    for line in String::from_utf8_lossy(&file_content).lines() {
+        let line = line.trim_start_matches('\u{feff}');
+
+        if line.starts_with('@') {
+            current_codepoint = None;
+        } else if let Some(annotation) = line.strip_prefix('\t') {
+            let char_data = match current_codepoint {
+                Some(codepoint) => unicode_char_data_map.get_mut(&codepoint),
+                None => None,
+            };
+
+            if let (Some(char_data), Some(annotation)) =
+                (char_data, parse_names_list_annotation(annotation))
+            {
+                char_data.annotations.push(annotation);
+            }
+        } else if let Some((hexcode, _)) = line.split_once('\t') {
+            current_codepoint = u32::from_str_radix(hexcode, 16).ok();
+        }
+    }
+}
+
+fn parse_names_list_annotation(annotation: &str) -> Option<(&'static str, String, Option<u32>)> {
+    let mut chars = annotation.chars();
+    let marker = chars.next()?;
+    let text = chars.as_str().trim();
+
+    match marker {
+        '=' => Some(("informal_alias", text.to_string(), None)),
+        '*' => Some(("note", text.to_string(), None)),
+        '#' => Some(("compatibility_decomposition", text.to_string(), None)),
+        'x' => {
+            // Cross references are written either as (name - 2212) or as a bare code point.
+            let (name, hexcode) = match text
+                .strip_prefix('(')
+                .and_then(|text| text.strip_suffix(')'))
+            {
+                Some(reference) => reference.rsplit_once(" - ")?,
+                None => ("", text.split_whitespace().next()?),
+            };
+            let codepoint = u32::from_str_radix(hexcode, 16).ok()?;
+            Some(("cross_reference", name.to_string(), Some(codepoint)))
+        }
+        _ => None,
+    }
+}
+
 fn save_to_database(
     target_directory_path: &Path,
     unicode_char_data_map: BTreeMap<u32, UnicodeCharData>,
+    newer_ages: Vec<(u32, u32, String)>,
+    unicode_version: &str,
 ) {
     let database_path = target_directory_path.join(DATABASE_FILE_NAME);
-    let database = Connection::open(database_path).expect("Database could not be created");
+
+    if database_path.is_file() {
+        remove_file(&database_path).expect("Outdated database could not be removed");
+    }
+
+    let mut database = Connection::open(database_path).expect("Database could not be created");
+
+    database
+        .execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS Metadata (
+                key TEXT NOT NULL PRIMARY KEY,
+                value TEXT NOT NULL
+            ) WITHOUT ROWID;
+            "#,
+            NO_PARAMS,
+        )
+        .expect("Database table could not be created");
+
+    database
+        .execute(
+            "INSERT INTO Metadata VALUES ('unicode_version', ?)",
+            params![unicode_version],
+        )
+        .expect("Database insert statement failed");
 
     database
         .execute(
@@ -175,41 +960,305 @@ fn save_to_database(
                 name TEXT NOT NULL,
                 category TEXT NOT NULL,
                 block TEXT NOT NULL,
-                age TEXT NOT NULL
+                age TEXT NOT NULL,
+                unicode_1_name TEXT,
+                iso_comment TEXT,
+                combining_class INTEGER NOT NULL,
+                decomposition TEXT,
+                is_composition_excluded INTEGER NOT NULL,
+                simple_uppercase TEXT,
+                simple_lowercase TEXT,
+                simple_titlecase TEXT,
+                simple_case_folding TEXT,
+                full_uppercase TEXT,
+                full_lowercase TEXT,
+                full_titlecase TEXT,
+                full_case_folding TEXT,
+                is_cased INTEGER NOT NULL,
+                is_case_ignorable INTEGER NOT NULL,
+                is_soft_dotted INTEGER NOT NULL,
+                bidi_class TEXT NOT NULL,
+                is_bidi_mirrored INTEGER NOT NULL,
+                bidi_mirroring_glyph INTEGER,
+                bidi_paired_bracket INTEGER,
+                bidi_paired_bracket_type TEXT,
+                numeric_type TEXT,
+                numeric_value TEXT
             ) WITHOUT ROWID;
             "#,
             NO_PARAMS,
         )
         .expect("Database table could not be created");
 
-    let entry_count: Result<u32, Error> =
-        database.query_row("SELECT COUNT(*) FROM UnicodeData", NO_PARAMS, |row| {
-            row.get(0)
-        });
+    // Formal name aliases from NameAliases.txt in the order of the file,
+    // typed as correction, control, alternate, figment or abbreviation.
+    database
+        .execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS NameAliases (
+                codepoint INTEGER NOT NULL,
+                alias TEXT NOT NULL,
+                type TEXT NOT NULL
+            );
+            "#,
+            NO_PARAMS,
+        )
+        .expect("Database table could not be created");
 
-    if entry_count.unwrap() > 0 {
-        return;
-    }
+    database
+        .execute(
+            "CREATE INDEX IF NOT EXISTS NameAliasesCodepoint ON NameAliases (codepoint)",
+            NO_PARAMS,
+        )
+        .expect("Database index could not be created");
+
+    // Annotations from NamesList.txt in the order of the file, typed as informal_alias,
+    // note, cross_reference or compatibility_decomposition. Only cross references
+    // have a referenced code point.
+    database
+        .execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS Annotations (
+                codepoint INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                type TEXT NOT NULL,
+                text TEXT NOT NULL,
+                reference INTEGER,
+                PRIMARY KEY (codepoint, position)
+            ) WITHOUT ROWID;
+            "#,
+            NO_PARAMS,
+        )
+        .expect("Database table could not be created");
+
+    // Case mappings from SpecialCasing.txt and CaseFolding.txt that only apply to a language,
+    // a context or both, typed as uppercase, lowercase, titlecase or case_folding.
+    // An empty mapping removes the character.
+    database
+        .execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS ConditionalCaseMappings (
+                codepoint INTEGER NOT NULL,
+                type TEXT NOT NULL,
+                mapping TEXT NOT NULL,
+                language TEXT,
+                context TEXT
+            );
+            "#,
+            NO_PARAMS,
+        )
+        .expect("Database table could not be created");
+
+    // Full-text index over names, aliases, legacy names and informal aliases whose rowids are the code points.
+    // It is contentless because the names are already stored in UnicodeData.
+    database
+        .execute(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS NameIndex USING fts5 (
+                name,
+                aliases,
+                legacy_names,
+                informal_aliases,
+                content = '',
+                tokenize = 'unicode61'
+            );
+            "#,
+            NO_PARAMS,
+        )
+        .expect("Database table could not be created");
 
-    let mut insert_statement = database
-        .prepare_cached(
-            r#"INSERT INTO UnicodeData VALUES (
-                ?,?,?,?,?
-            )"#,
+    // Distinct words of names and formal aliases that fuzzy name search compares with misspelled query words.
+    // The code points ending derived names and labels such as CJK UNIFIED IDEOGRAPH-4E00 are left out,
+    // because they are only ever looked up exactly, which the full-text index already covers.
+    database
+        .execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS NameWords (
+                word TEXT NOT NULL PRIMARY KEY
+            ) WITHOUT ROWID;
+            "#,
+            NO_PARAMS,
+        )
+        .expect("Database table could not be created");
+
+    database
+        .execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS NewerAges (
+                first_codepoint INTEGER NOT NULL PRIMARY KEY,
+                last_codepoint INTEGER NOT NULL,
+                age TEXT NOT NULL
+            );
+            "#,
+            NO_PARAMS,
         )
-        .unwrap();
-
-    for (codepoint, char_data) in unicode_char_data_map.iter() {
-        insert_statement
-            .execute(params![
-                *codepoint,
-                &char_data.name,
-                &char_data.category,
-                &char_data.block,
-                &char_data.age,
-            ])
-            .expect("Database insert statement failed");
+        .expect("Database table could not be created");
+
+    let transaction = database
+        .transaction()
+        .expect("Database transaction could not be started");
+
+    {
+        let mut newer_age_statement = transaction
+            .prepare_cached("INSERT INTO NewerAges VALUES (?,?,?)")
+            .unwrap();
+
+        for (first_codepoint, last_codepoint, age) in newer_ages.iter() {
+            newer_age_statement
+                .execute(params![*first_codepoint, *last_codepoint, age])
+                .expect("Database insert statement failed");
+        }
+
+        let mut insert_statement = transaction
+            .prepare_cached(
+                r#"INSERT INTO UnicodeData VALUES (
+                    ?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?
+                )"#,
+            )
+            .unwrap();
+
+        let mut index_statement = transaction
+            .prepare_cached(
+                "INSERT INTO NameIndex (rowid, name, aliases, legacy_names, informal_aliases) \
+                 VALUES (?,?,?,?,?)",
+            )
+            .unwrap();
+
+        let mut annotation_statement = transaction
+            .prepare_cached("INSERT INTO Annotations VALUES (?,?,?,?,?)")
+            .unwrap();
+
+        let mut alias_statement = transaction
+            .prepare_cached("INSERT INTO NameAliases VALUES (?,?,?)")
+            .unwrap();
+
+        let mut case_mapping_statement = transaction
+            .prepare_cached("INSERT INTO ConditionalCaseMappings VALUES (?,?,?,?,?)")
+            .unwrap();
+
+        let mut word_statement = transaction
+            .prepare_cached("INSERT OR IGNORE INTO NameWords VALUES (?)")
+            .unwrap();
+
+        for (codepoint, char_data) in unicode_char_data_map.iter() {
+            insert_statement
+                .execute(params![
+                    *codepoint,
+                    &char_data.name,
+                    &char_data.category,
+                    &char_data.block,
+                    &char_data.age,
+                    &char_data.unicode_1_name,
+                    &char_data.iso_comment,
+                    char_data.combining_class,
+                    &char_data.decomposition,
+                    char_data.is_composition_excluded,
+                    &char_data.simple_uppercase,
+                    &char_data.simple_lowercase,
+                    &char_data.simple_titlecase,
+                    &char_data.simple_case_folding,
+                    &char_data.full_uppercase,
+                    &char_data.full_lowercase,
+                    &char_data.full_titlecase,
+                    &char_data.full_case_folding,
+                    char_data.is_cased,
+                    char_data.is_case_ignorable,
+                    char_data.is_soft_dotted,
+                    &char_data.bidi_class,
+                    char_data.is_bidi_mirrored,
+                    char_data.bidi_mirroring_glyph,
+                    char_data.bidi_paired_bracket,
+                    &char_data.bidi_paired_bracket_type,
+                    &char_data.numeric_type,
+                    &char_data.numeric_value,
+                ])
+                .expect("Database insert statement failed");
+
+            for (mapping_type, mapping, language, context) in
+                char_data.conditional_case_mappings.iter()
+            {
+                case_mapping_statement
+                    .execute(params![
+                        *codepoint,
+                        mapping_type,
+                        mapping,
+                        language,
+                        context
+                    ])
+                    .expect("Database insert statement failed");
+            }
+
+            for (alias, alias_type) in char_data.aliases.iter() {
+                alias_statement
+                    .execute(params![*codepoint, alias, alias_type])
+                    .expect("Database insert statement failed");
+            }
+
+            let aliases = char_data
+                .aliases
+                .iter()
+                .map(|(alias, _)| alias.as_str())
+                .collect::<Vec<_>>();
+            let legacy_names = char_data
+                .unicode_1_name
+                .iter()
+                .chain(char_data.iso_comment.iter())
+                .map(|legacy_name| legacy_name.as_str())
+                .collect::<Vec<_>>();
+            let informal_aliases = char_data
+                .annotations
+                .iter()
+                .filter(|(annotation_type, _, _)| *annotation_type == "informal_alias")
+                .map(|(_, text, _)| text.as_str())
+                .collect::<Vec<_>>();
+
+            for (position, (annotation_type, text, reference)) in
+                char_data.annotations.iter().enumerate()
+            {
+                annotation_statement
+                    .execute(params![
+                        *codepoint,
+                        position as u32,
+                        annotation_type,
+                        text,
+                        reference
+                    ])
+                    .expect("Database insert statement failed");
+            }
+
+            let hexcode = format!("{:04X}", codepoint);
+
+            for word in char_data
+                .name
+                .split(|c: char| c.is_whitespace() || c == '-')
+                .chain(
+                    aliases
+                        .iter()
+                        .flat_map(|alias| alias.split(|c: char| c.is_whitespace() || c == '-')),
+                )
+                .map(|word| word.to_uppercase())
+                .filter(|word| !word.is_empty() && word.trim_end_matches('>') != hexcode)
+            {
+                word_statement
+                    .execute(params![word])
+                    .expect("Database insert statement failed");
+            }
+
+            index_statement
+                .execute(params![
+                    *codepoint,
+                    &char_data.name,
+                    aliases.join("\n"),
+                    legacy_names.join("\n"),
+                    informal_aliases.join("\n"),
+                ])
+                .expect("Database insert statement failed");
+        }
     }
+
+    transaction
+        .commit()
+        .expect("Database transaction could not be committed");
 }
 
 fn compress_database(target_directory_path: &Path) {
@@ -233,12 +1282,22 @@ fn compress_database(target_directory_path: &Path) {
         .expect("Database could not be written to zip file");
 }
 
-fn open_csv_file_reader(target_directory_path: &Path, file_name: &str) -> Reader<File> {
+// The checksum becomes part of the database file name at runtime,
+// so that a database unzipped by an earlier build is never reused.
+fn export_database_checksum(target_directory_path: &Path) {
+    let database_path = target_directory_path.join(DATABASE_FILE_NAME);
+    let database = read(database_path).expect("Database could not be read as bytes");
+    let checksum = format!("{:x}", Sha256::digest(&database));
+
+    println!("cargo:rustc-env=CHR_DATABASE_CHECKSUM={}", &checksum[..16]);
+}
+
+fn open_csv_file_reader(ucd_directory_path: &Path, file_name: &str) -> Reader<File> {
     ReaderBuilder::new()
         .delimiter(b';')
         .has_headers(false)
         .flexible(true)
-        .from_path(target_directory_path.join(file_name))
+        .from_path(ucd_directory_path.join(file_name))
         .unwrap_or_else(|_| panic!("File {} could not be opened for reading", file_name))
 }
 
@@ -250,9 +1309,9 @@ fn codepoint_range(codepoints: &str) -> RangeInclusive<u32> {
     if codepoints.contains("..") {
         let start_and_end = codepoints
             .split("..")
-            .map(|hexcode| to_decimal_number(hexcode))
+            .map(to_decimal_number)
             .collect::<Vec<_>>();
-        let start = *start_and_end.get(0).unwrap();
+        let start = *start_and_end.first().unwrap();
         let end = *start_and_end.get(1).unwrap();
         start..=end
     } else {
@@ -270,9 +1329,9 @@ struct UnicodeDataFileRow {
     canonical_combining_class: u32,
     bidi_class: String,
     decomposition_type: Option<String>,
-    numeric_value_1: Option<u32>,
-    numeric_value_2: Option<u32>,
-    numeric_value_3: Option<String>,
+    decimal_digit_value: Option<u32>,
+    digit_value: Option<u32>,
+    numeric_value: Option<String>,
     bidi_mirrored: String,
     unicode_1_name: Option<String>,
     iso_comment: Option<String>,
@@ -282,20 +1341,91 @@ struct UnicodeDataFileRow {
 }
 
 #[derive(Default)]
+#[allow(dead_code)]
 struct UnicodeCharData {
     codepoint: u32,
     name: String,
     category: String,
     block: String,
     age: String,
+    unicode_1_name: Option<String>,
+    iso_comment: Option<String>,
+    combining_class: u32,
+    decomposition: Option<String>,
+    is_composition_excluded: bool,
+    simple_uppercase: Option<String>,
+    simple_lowercase: Option<String>,
+    simple_titlecase: Option<String>,
+    simple_case_folding: Option<String>,
+    full_uppercase: Option<String>,
+    full_lowercase: Option<String>,
+    full_titlecase: Option<String>,
+    full_case_folding: Option<String>,
+    is_cased: bool,
+    is_case_ignorable: bool,
+    is_soft_dotted: bool,
+    bidi_class: String,
+    is_bidi_mirrored: bool,
+    bidi_mirroring_glyph: Option<u32>,
+    bidi_paired_bracket: Option<u32>,
+    bidi_paired_bracket_type: Option<String>,
+    numeric_type: Option<&'static str>,
+    numeric_value: Option<String>,
+    aliases: Vec<(String, String)>,
+    annotations: Vec<(&'static str, String, Option<u32>)>,
+    conditional_case_mappings: Vec<(&'static str, String, Option<String>, Option<String>)>,
 }
 
 impl UnicodeCharData {
     fn from(unicode_data_file_row: &UnicodeDataFileRow, codepoint: u32, name: &str) -> Self {
-        let mut unicode_char_data = UnicodeCharData::default();
-        unicode_char_data.codepoint = codepoint;
-        unicode_char_data.name = name.to_string();
-        unicode_char_data.category = unicode_data_file_row.category.clone();
-        unicode_char_data
+        // An empty titlecase mapping means that it equals the uppercase mapping.
+        // Mappings of characters to themselves are left out.
+        let simple_mapping = |mapping: &Option<String>| {
+            mapping
+                .clone()
+                .filter(|mapping| to_decimal_number(mapping) != codepoint)
+        };
+        let simple_uppercase = simple_mapping(&unicode_data_file_row.uppercase_mapping);
+        let simple_lowercase = simple_mapping(&unicode_data_file_row.lowercase_mapping);
+        let simple_titlecase = simple_mapping(
+            &unicode_data_file_row
+                .titlecase_mapping
+                .clone()
+                .or_else(|| unicode_data_file_row.uppercase_mapping.clone()),
+        );
+
+        // The numeric type is De for decimal digits, Di for other digits
+        // and Nu for any other numbers including fractions such as 1/2.
+        let numeric_type = match (
+            unicode_data_file_row.decimal_digit_value,
+            unicode_data_file_row.digit_value,
+            &unicode_data_file_row.numeric_value,
+        ) {
+            (_, _, None) => None,
+            (Some(_), _, _) => Some("De"),
+            (None, Some(_), _) => Some("Di"),
+            (None, None, _) => Some("Nu"),
+        };
+
+        UnicodeCharData {
+            codepoint,
+            name: name.to_string(),
+            category: unicode_data_file_row.category.clone(),
+            unicode_1_name: unicode_data_file_row.unicode_1_name.clone(),
+            iso_comment: unicode_data_file_row.iso_comment.clone(),
+            combining_class: unicode_data_file_row.canonical_combining_class,
+            decomposition: unicode_data_file_row.decomposition_type.clone(),
+            bidi_class: unicode_data_file_row.bidi_class.clone(),
+            is_bidi_mirrored: unicode_data_file_row.bidi_mirrored == "Y",
+            numeric_type,
+            numeric_value: unicode_data_file_row.numeric_value.clone(),
+            full_uppercase: simple_uppercase.clone(),
+            full_lowercase: simple_lowercase.clone(),
+            full_titlecase: simple_titlecase.clone(),
+            simple_uppercase,
+            simple_lowercase,
+            simple_titlecase,
+            ..Default::default()
+        }
     }
 }
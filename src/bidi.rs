@@ -0,0 +1,1057 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bidi_class::BidiClass;
+use crate::decomposition::parse_decomposition_mapping;
+use rusqlite::{params, Connection, NO_PARAMS};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// The maximum explicit embedding level of rule BD2 in UAX #9.
+const MAX_DEPTH: u8 = 125;
+
+/// The maximum nesting of bracket pairs of rule BD16 in UAX #9.
+const MAX_BRACKET_PAIRING_DEPTH: usize = 63;
+
+#[derive(Clone, Copy)]
+pub enum ParagraphDirection {
+    LeftToRight,
+    RightToLeft,
+    Auto,
+}
+
+impl FromStr for ParagraphDirection {
+    type Err = String;
+
+    fn from_str(direction: &str) -> Result<Self, Self::Err> {
+        match direction.to_ascii_lowercase().as_str() {
+            "ltr" => Ok(ParagraphDirection::LeftToRight),
+            "rtl" => Ok(ParagraphDirection::RightToLeft),
+            "auto" => Ok(ParagraphDirection::Auto),
+            _ => Err(format!(
+                "unknown direction '{}', expected ltr, rtl or auto",
+                direction
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+}
+
+impl Direction {
+    pub fn of_level(level: u8) -> Self {
+        if level % 2 == 0 {
+            Direction::LeftToRight
+        } else {
+            Direction::RightToLeft
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let direction = match self {
+            Direction::LeftToRight => "left-to-right",
+            Direction::RightToLeft => "right-to-left",
+        };
+        write!(f, "{}", direction)
+    }
+}
+
+/// How a character takes part in the explicit embeddings, overrides and isolates
+/// of rules X1 to X8, which is worth pointing out when debugging mixed directions.
+#[derive(Clone, Copy)]
+pub enum ExplicitFormatting {
+    Embedding(Direction),
+    Override(Direction),
+    Isolate(Direction),
+    EmbeddingEnd,
+    OverrideEnd,
+    IsolateEnd,
+    Overridden(BidiClass),
+    Overflow,
+    Unmatched,
+}
+
+impl Display for ExplicitFormatting {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ExplicitFormatting::Embedding(direction) => {
+                write!(f, "starts {} embedding", direction)
+            }
+            ExplicitFormatting::Override(direction) => write!(f, "starts {} override", direction),
+            ExplicitFormatting::Isolate(direction) => write!(f, "starts {} isolate", direction),
+            ExplicitFormatting::EmbeddingEnd => write!(f, "ends embedding"),
+            ExplicitFormatting::OverrideEnd => write!(f, "ends override"),
+            ExplicitFormatting::IsolateEnd => write!(f, "ends isolate"),
+            ExplicitFormatting::Overridden(bidi_class) => {
+                write!(f, "overridden to {}", bidi_class.as_ref())
+            }
+            ExplicitFormatting::Overflow => write!(f, "ignored beyond maximum depth"),
+            ExplicitFormatting::Unmatched => write!(f, "ignored without initiator"),
+        }
+    }
+}
+
+pub struct BidiCharacter {
+    pub codepoint: u32,
+    pub bidi_class: BidiClass,
+    pub resolved_class: BidiClass,
+    /// The resolved embedding level, which is none
+    /// for the characters removed by rule X9.
+    pub level: Option<u8>,
+    pub formatting: Option<ExplicitFormatting>,
+}
+
+/// A maximal substring of characters with the same resolved level,
+/// given as the positions of its first and last character within the paragraph.
+pub struct LevelRun {
+    pub start: usize,
+    pub end: usize,
+    pub level: u8,
+}
+
+pub struct BidiParagraph {
+    /// The position of the first character of the paragraph within the text.
+    pub offset: usize,
+    pub level: u8,
+    pub characters: Vec<BidiCharacter>,
+    pub runs: Vec<LevelRun>,
+    /// The positions of the characters within the paragraph in display order,
+    /// leaving out the characters removed by rule X9.
+    pub visual_order: Vec<usize>,
+    /// The characters in display order with mirrored glyphs applied by rule L4.
+    pub visual_text: String,
+}
+
+struct Bracket {
+    is_opening: bool,
+    canonical: u32,
+    canonical_pair: u32,
+}
+
+/// Runs the Unicode Bidirectional Algorithm of UAX #9 on single lines of text
+/// with the bidi classes, mirroring glyphs and paired brackets of the database.
+/// Code points that are not in the database are treated as `L`.
+pub struct BidiResolver {
+    bidi_classes: HashMap<u32, BidiClass>,
+    mirroring_glyphs: HashMap<u32, u32>,
+    brackets: HashMap<u32, Bracket>,
+}
+
+impl BidiResolver {
+    /// Loads the properties of the characters in `text` and of all paired brackets.
+    pub fn load(database: &Connection, text: &str) -> Self {
+        let mut resolver = Self {
+            bidi_classes: HashMap::new(),
+            mirroring_glyphs: HashMap::new(),
+            brackets: HashMap::new(),
+        };
+        let mut codepoints = text.chars().map(|c| c as u32).collect::<Vec<_>>();
+
+        codepoints.sort_unstable();
+        codepoints.dedup();
+
+        // The code points go through a temporary table, as a text may contain
+        // more distinct characters than parameters can be bound to a statement.
+        database
+            .execute_batch(
+                "DROP TABLE IF EXISTS temp.BidiText; \
+                 CREATE TEMP TABLE BidiText (codepoint INTEGER PRIMARY KEY);",
+            )
+            .unwrap();
+
+        let mut insert_statement = database
+            .prepare("INSERT INTO temp.BidiText VALUES (?)")
+            .unwrap();
+
+        for codepoint in codepoints {
+            insert_statement.execute(params![codepoint]).unwrap();
+        }
+
+        let mut statement = database
+            .prepare(
+                "SELECT codepoint, bidi_class, bidi_mirroring_glyph \
+                 FROM temp.BidiText JOIN UnicodeData USING (codepoint)",
+            )
+            .unwrap();
+        let mut rows = statement.query(NO_PARAMS).unwrap();
+
+        while let Some(row) = rows.next().unwrap() {
+            let codepoint: u32 = row.get_unwrap(0);
+            let bidi_class: String = row.get_unwrap(1);
+
+            resolver
+                .bidi_classes
+                .insert(codepoint, BidiClass::from_str(&bidi_class).unwrap());
+
+            if let Some(mirroring_glyph) = row.get_unwrap(2) {
+                resolver.mirroring_glyphs.insert(codepoint, mirroring_glyph);
+            }
+        }
+
+        // Brackets are compared by their canonical equivalents as required by rule BD16,
+        // so that U+2329 pairs with U+3009 just like U+3008 does.
+        let mut statement = database
+            .prepare(
+                "SELECT codepoint, decomposition, bidi_paired_bracket, bidi_paired_bracket_type \
+                 FROM UnicodeData WHERE bidi_paired_bracket IS NOT NULL",
+            )
+            .unwrap();
+        let mut rows = statement.query(NO_PARAMS).unwrap();
+        let mut bracket_rows = vec![];
+        let mut canonical_brackets = HashMap::new();
+
+        while let Some(row) = rows.next().unwrap() {
+            let codepoint: u32 = row.get_unwrap(0);
+            let decomposition: Option<String> = row.get_unwrap(1);
+            let paired_bracket: u32 = row.get_unwrap(2);
+            let paired_bracket_type: String = row.get_unwrap(3);
+
+            if let Some((None, decomposed)) =
+                decomposition.as_deref().map(parse_decomposition_mapping)
+            {
+                if let [canonical] = decomposed[..] {
+                    canonical_brackets.insert(codepoint, canonical);
+                }
+            }
+
+            bracket_rows.push((codepoint, paired_bracket, paired_bracket_type == "o"));
+        }
+
+        let canonical = |codepoint: u32| *canonical_brackets.get(&codepoint).unwrap_or(&codepoint);
+
+        for (codepoint, paired_bracket, is_opening) in bracket_rows {
+            resolver.brackets.insert(
+                codepoint,
+                Bracket {
+                    is_opening,
+                    canonical: canonical(codepoint),
+                    canonical_pair: canonical(paired_bracket),
+                },
+            );
+        }
+
+        resolver
+    }
+
+    /// Splits `text` into paragraphs after each paragraph separator
+    /// and resolves each of them as a single line.
+    pub fn resolve(&self, text: &str, direction: ParagraphDirection) -> Vec<BidiParagraph> {
+        let mut paragraphs = vec![];
+        let mut codepoints = vec![];
+        let mut offset = 0;
+
+        for c in text.chars() {
+            codepoints.push(c as u32);
+
+            if self.bidi_class(c as u32) == BidiClass::B {
+                let paragraph_length = codepoints.len();
+                paragraphs.push(self.resolve_paragraph(codepoints, offset, direction));
+                codepoints = vec![];
+                offset += paragraph_length;
+            }
+        }
+
+        if !codepoints.is_empty() {
+            paragraphs.push(self.resolve_paragraph(codepoints, offset, direction));
+        }
+
+        paragraphs
+    }
+
+    fn bidi_class(&self, codepoint: u32) -> BidiClass {
+        *self.bidi_classes.get(&codepoint).unwrap_or(&BidiClass::L)
+    }
+
+    fn resolve_paragraph(
+        &self,
+        codepoints: Vec<u32>,
+        offset: usize,
+        direction: ParagraphDirection,
+    ) -> BidiParagraph {
+        let original_classes = codepoints
+            .iter()
+            .map(|codepoint| self.bidi_class(*codepoint))
+            .collect::<Vec<_>>();
+        let matching_pdis = find_matching_pdis(&original_classes);
+
+        // Rules P2 and P3
+        let paragraph_level = match direction {
+            ParagraphDirection::LeftToRight => 0,
+            ParagraphDirection::RightToLeft => 1,
+            ParagraphDirection::Auto => {
+                find_first_strong_level(&original_classes, &matching_pdis, 0, codepoints.len())
+                    .unwrap_or(0)
+            }
+        };
+
+        let mut explicit =
+            resolve_explicit_levels(&original_classes, &matching_pdis, paragraph_level);
+        let is_removed = original_classes
+            .iter()
+            .map(|bidi_class| is_removed_by_x9(*bidi_class))
+            .collect::<Vec<_>>();
+
+        for sequence in find_isolating_run_sequences(
+            &original_classes,
+            &matching_pdis,
+            &explicit.levels,
+            &is_removed,
+            paragraph_level,
+        ) {
+            self.resolve_sequence(&sequence, &codepoints, &original_classes, &mut explicit);
+        }
+
+        reset_whitespace_levels(
+            &original_classes,
+            &is_removed,
+            &mut explicit.levels,
+            paragraph_level,
+        );
+
+        let levels = explicit
+            .levels
+            .iter()
+            .zip(&is_removed)
+            .map(|(level, is_removed)| if *is_removed { None } else { Some(*level) })
+            .collect::<Vec<_>>();
+        let visual_order = reorder(&levels);
+        let visual_text = visual_order
+            .iter()
+            .filter_map(|idx| {
+                let codepoint = codepoints[*idx];
+                let is_mirrored = matches!(levels[*idx], Some(level) if level % 2 == 1);
+                let glyph = if is_mirrored {
+                    self.mirroring_glyphs.get(&codepoint).copied()
+                } else {
+                    None
+                };
+                std::char::from_u32(glyph.unwrap_or(codepoint))
+            })
+            .collect();
+        let runs = find_level_runs(&levels);
+        let characters = codepoints
+            .into_iter()
+            .enumerate()
+            .map(|(idx, codepoint)| BidiCharacter {
+                codepoint,
+                bidi_class: original_classes[idx],
+                resolved_class: explicit.classes[idx],
+                level: levels[idx],
+                formatting: explicit.formatting[idx],
+            })
+            .collect();
+
+        BidiParagraph {
+            offset,
+            level: paragraph_level,
+            characters,
+            runs,
+            visual_order,
+            visual_text,
+        }
+    }
+
+    /// Applies the rules W1 to W7, N0 to N2 and I1 to I2 to an isolating run sequence.
+    fn resolve_sequence(
+        &self,
+        sequence: &IsolatingRunSequence,
+        codepoints: &[u32],
+        original_classes: &[BidiClass],
+        explicit: &mut ExplicitResolution,
+    ) {
+        let indices = &sequence.indices;
+        let mut classes = indices
+            .iter()
+            .map(|idx| explicit.classes[*idx])
+            .collect::<Vec<_>>();
+        let level = explicit.levels[indices[0]];
+        let embedding_direction = strong_class_of_level(level);
+
+        resolve_weak_types(&mut classes, sequence.sos);
+
+        let bracket_pairs = self.find_bracket_pairs(indices, codepoints, &classes);
+
+        for (opening, closing) in bracket_pairs {
+            let mut has_embedding_direction = false;
+            let mut has_opposite_direction = false;
+
+            for bidi_class in &classes[opening + 1..closing] {
+                match strong_direction(*bidi_class) {
+                    Some(direction) if direction == embedding_direction => {
+                        has_embedding_direction = true
+                    }
+                    Some(_) => has_opposite_direction = true,
+                    None => {}
+                }
+            }
+
+            let resolved_class = if has_embedding_direction {
+                embedding_direction
+            } else if has_opposite_direction {
+                let preceding_direction = classes[..opening]
+                    .iter()
+                    .rev()
+                    .find_map(|bidi_class| strong_direction(*bidi_class))
+                    .unwrap_or(sequence.sos);
+
+                if preceding_direction == embedding_direction {
+                    embedding_direction
+                } else {
+                    preceding_direction
+                }
+            } else {
+                continue;
+            };
+
+            for bracket in [opening, closing] {
+                classes[bracket] = resolved_class;
+
+                // Nonspacing marks following a bracket take on its new type.
+                for idx in bracket + 1..indices.len() {
+                    if original_classes[indices[idx]] != BidiClass::Nsm {
+                        break;
+                    }
+                    classes[idx] = resolved_class;
+                }
+            }
+        }
+
+        resolve_neutral_types(
+            &mut classes,
+            sequence.sos,
+            sequence.eos,
+            embedding_direction,
+        );
+
+        // Rules I1 and I2
+        for (idx, bidi_class) in indices.iter().zip(&classes) {
+            let level = &mut explicit.levels[*idx];
+
+            *level += match (*level % 2 == 0, bidi_class) {
+                (true, BidiClass::R) => 1,
+                (true, BidiClass::An) | (true, BidiClass::En) => 2,
+                (false, BidiClass::L) | (false, BidiClass::An) | (false, BidiClass::En) => 1,
+                _ => 0,
+            };
+            explicit.classes[*idx] = *bidi_class;
+        }
+    }
+
+    /// Identifies the bracket pairs of an isolating run sequence as in rule BD16
+    /// and returns their positions within the sequence ordered by opening bracket.
+    fn find_bracket_pairs(
+        &self,
+        indices: &[usize],
+        codepoints: &[u32],
+        classes: &[BidiClass],
+    ) -> Vec<(usize, usize)> {
+        let mut openings: Vec<(u32, usize)> = vec![];
+        let mut pairs = vec![];
+
+        for (position, idx) in indices.iter().enumerate() {
+            if classes[position] != BidiClass::On {
+                continue;
+            }
+
+            let bracket = match self.brackets.get(&codepoints[*idx]) {
+                Some(bracket) => bracket,
+                None => continue,
+            };
+
+            if bracket.is_opening {
+                if openings.len() == MAX_BRACKET_PAIRING_DEPTH {
+                    break;
+                }
+                openings.push((bracket.canonical_pair, position));
+            } else if let Some(stack_idx) = openings
+                .iter()
+                .rposition(|(closing, _)| *closing == bracket.canonical)
+            {
+                pairs.push((openings[stack_idx].1, position));
+                openings.truncate(stack_idx);
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+/// The embedding levels, the character types and the formatting roles
+/// resulting from the rules X1 to X8.
+struct ExplicitResolution {
+    levels: Vec<u8>,
+    classes: Vec<BidiClass>,
+    formatting: Vec<Option<ExplicitFormatting>>,
+}
+
+#[derive(Clone, Copy)]
+struct DirectionalStatus {
+    level: u8,
+    override_class: Option<BidiClass>,
+    is_isolate: bool,
+}
+
+struct IsolatingRunSequence {
+    indices: Vec<usize>,
+    sos: BidiClass,
+    eos: BidiClass,
+}
+
+fn is_isolate_initiator(bidi_class: BidiClass) -> bool {
+    matches!(bidi_class, BidiClass::Lri | BidiClass::Rli | BidiClass::Fsi)
+}
+
+fn is_removed_by_x9(bidi_class: BidiClass) -> bool {
+    matches!(
+        bidi_class,
+        BidiClass::Lre
+            | BidiClass::Rle
+            | BidiClass::Lro
+            | BidiClass::Rlo
+            | BidiClass::Pdf
+            | BidiClass::Bn
+    )
+}
+
+fn strong_class_of_level(level: u8) -> BidiClass {
+    if level % 2 == 0 {
+        BidiClass::L
+    } else {
+        BidiClass::R
+    }
+}
+
+/// Returns `L` or `R` for the types that count as strong within bracket pairs
+/// and sequences of neutrals, where numbers count as `R`.
+fn strong_direction(bidi_class: BidiClass) -> Option<BidiClass> {
+    match bidi_class {
+        BidiClass::L => Some(BidiClass::L),
+        BidiClass::R | BidiClass::Al | BidiClass::En | BidiClass::An => Some(BidiClass::R),
+        _ => None,
+    }
+}
+
+/// Returns the position of the matching PDI of each isolate initiator as in rule BD9.
+fn find_matching_pdis(classes: &[BidiClass]) -> Vec<Option<usize>> {
+    let mut matching_pdis = vec![None; classes.len()];
+    let mut initiators = vec![];
+
+    for (idx, bidi_class) in classes.iter().enumerate() {
+        if is_isolate_initiator(*bidi_class) {
+            initiators.push(idx);
+        } else if *bidi_class == BidiClass::Pdi {
+            if let Some(initiator) = initiators.pop() {
+                matching_pdis[initiator] = Some(idx);
+            }
+        }
+    }
+
+    matching_pdis
+}
+
+/// Finds the first character of type L, R or AL between `start` and `end`
+/// that is not part of an isolate, as in rules P2 and P3.
+fn find_first_strong_level(
+    classes: &[BidiClass],
+    matching_pdis: &[Option<usize>],
+    start: usize,
+    end: usize,
+) -> Option<u8> {
+    let mut idx = start;
+
+    while idx < end {
+        match classes[idx] {
+            BidiClass::L => return Some(0),
+            BidiClass::R | BidiClass::Al => return Some(1),
+            bidi_class if is_isolate_initiator(bidi_class) => {
+                idx = matching_pdis[idx].unwrap_or(end);
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    None
+}
+
+/// Applies the rules X1 to X8 with the directional status stack.
+fn resolve_explicit_levels(
+    classes: &[BidiClass],
+    matching_pdis: &[Option<usize>],
+    paragraph_level: u8,
+) -> ExplicitResolution {
+    let mut resolution = ExplicitResolution {
+        levels: vec![paragraph_level; classes.len()],
+        classes: classes.to_vec(),
+        formatting: vec![None; classes.len()],
+    };
+    let mut stack = vec![DirectionalStatus {
+        level: paragraph_level,
+        override_class: None,
+        is_isolate: false,
+    }];
+    let mut overflow_isolate_count = 0;
+    let mut overflow_embedding_count = 0;
+    let mut valid_isolate_count = 0;
+
+    for (idx, bidi_class) in classes.iter().enumerate() {
+        let last_status = *stack.last().unwrap();
+
+        match bidi_class {
+            BidiClass::Rle | BidiClass::Lre | BidiClass::Rlo | BidiClass::Lro => {
+                let is_rtl = matches!(bidi_class, BidiClass::Rle | BidiClass::Rlo);
+                let level = next_level(last_status.level, is_rtl);
+
+                resolution.levels[idx] = last_status.level;
+
+                if level <= MAX_DEPTH
+                    && overflow_isolate_count == 0
+                    && overflow_embedding_count == 0
+                {
+                    let override_class = match bidi_class {
+                        BidiClass::Rlo => Some(BidiClass::R),
+                        BidiClass::Lro => Some(BidiClass::L),
+                        _ => None,
+                    };
+                    let direction = Direction::of_level(level);
+
+                    stack.push(DirectionalStatus {
+                        level,
+                        override_class,
+                        is_isolate: false,
+                    });
+                    resolution.formatting[idx] = Some(match override_class {
+                        Some(_) => ExplicitFormatting::Override(direction),
+                        None => ExplicitFormatting::Embedding(direction),
+                    });
+                } else {
+                    if overflow_isolate_count == 0 {
+                        overflow_embedding_count += 1;
+                    }
+                    resolution.formatting[idx] = Some(ExplicitFormatting::Overflow);
+                }
+            }
+            BidiClass::Rli | BidiClass::Lri | BidiClass::Fsi => {
+                resolution.levels[idx] = last_status.level;
+
+                if let Some(override_class) = last_status.override_class {
+                    resolution.classes[idx] = override_class;
+                }
+
+                let is_rtl = match bidi_class {
+                    BidiClass::Rli => true,
+                    BidiClass::Lri => false,
+                    _ => {
+                        let end = matching_pdis[idx].unwrap_or(classes.len());
+                        find_first_strong_level(classes, matching_pdis, idx + 1, end) == Some(1)
+                    }
+                };
+                let level = next_level(last_status.level, is_rtl);
+
+                if level <= MAX_DEPTH
+                    && overflow_isolate_count == 0
+                    && overflow_embedding_count == 0
+                {
+                    valid_isolate_count += 1;
+                    stack.push(DirectionalStatus {
+                        level,
+                        override_class: None,
+                        is_isolate: true,
+                    });
+                    resolution.formatting[idx] =
+                        Some(ExplicitFormatting::Isolate(Direction::of_level(level)));
+                } else {
+                    overflow_isolate_count += 1;
+                    resolution.formatting[idx] = Some(ExplicitFormatting::Overflow);
+                }
+            }
+            BidiClass::Pdi => {
+                let formatting = if overflow_isolate_count > 0 {
+                    overflow_isolate_count -= 1;
+                    ExplicitFormatting::Overflow
+                } else if valid_isolate_count == 0 {
+                    ExplicitFormatting::Unmatched
+                } else {
+                    overflow_embedding_count = 0;
+                    while !stack.last().unwrap().is_isolate {
+                        stack.pop();
+                    }
+                    stack.pop();
+                    valid_isolate_count -= 1;
+                    ExplicitFormatting::IsolateEnd
+                };
+                let last_status = *stack.last().unwrap();
+
+                resolution.levels[idx] = last_status.level;
+                resolution.formatting[idx] = Some(formatting);
+
+                if let Some(override_class) = last_status.override_class {
+                    resolution.classes[idx] = override_class;
+                }
+            }
+            BidiClass::Pdf => {
+                resolution.levels[idx] = last_status.level;
+                resolution.formatting[idx] = Some(if overflow_isolate_count > 0 {
+                    ExplicitFormatting::Overflow
+                } else if overflow_embedding_count > 0 {
+                    overflow_embedding_count -= 1;
+                    ExplicitFormatting::Overflow
+                } else if !last_status.is_isolate && stack.len() >= 2 {
+                    stack.pop();
+                    match last_status.override_class {
+                        Some(_) => ExplicitFormatting::OverrideEnd,
+                        None => ExplicitFormatting::EmbeddingEnd,
+                    }
+                } else {
+                    ExplicitFormatting::Unmatched
+                });
+            }
+            BidiClass::B => resolution.levels[idx] = paragraph_level,
+            BidiClass::Bn => resolution.levels[idx] = last_status.level,
+            _ => {
+                resolution.levels[idx] = last_status.level;
+
+                if let Some(override_class) = last_status.override_class {
+                    resolution.classes[idx] = override_class;
+
+                    if override_class != *bidi_class {
+                        resolution.formatting[idx] =
+                            Some(ExplicitFormatting::Overridden(override_class));
+                    }
+                }
+            }
+        }
+    }
+
+    resolution
+}
+
+/// Returns the least odd level for right-to-left text
+/// or the least even level for left-to-right text above `level`.
+fn next_level(level: u8, is_rtl: bool) -> u8 {
+    match (level % 2 == 0, is_rtl) {
+        (true, true) | (false, false) => level + 1,
+        _ => level + 2,
+    }
+}
+
+/// Splits the characters that are not removed by rule X9 into level runs
+/// and connects them across isolates as in rules BD13 and X10.
+fn find_isolating_run_sequences(
+    classes: &[BidiClass],
+    matching_pdis: &[Option<usize>],
+    levels: &[u8],
+    is_removed: &[bool],
+    paragraph_level: u8,
+) -> Vec<IsolatingRunSequence> {
+    let retained = (0..classes.len())
+        .filter(|idx| !is_removed[*idx])
+        .collect::<Vec<_>>();
+    let mut level_runs: Vec<Vec<usize>> = vec![];
+
+    for idx in &retained {
+        match level_runs.last_mut() {
+            Some(run) if levels[run[0]] == levels[*idx] => run.push(*idx),
+            _ => level_runs.push(vec![*idx]),
+        }
+    }
+
+    let run_starts = level_runs
+        .iter()
+        .enumerate()
+        .map(|(run_idx, run)| (run[0], run_idx))
+        .collect::<HashMap<_, _>>();
+    let mut is_matched_pdi = vec![false; classes.len()];
+
+    for pdi in matching_pdis.iter().flatten() {
+        is_matched_pdi[*pdi] = true;
+    }
+
+    let level_of_neighbor = |idx: usize, step: isize| {
+        let position = retained.binary_search(&idx).unwrap() as isize + step;
+        if position < 0 {
+            paragraph_level
+        } else {
+            retained
+                .get(position as usize)
+                .map_or(paragraph_level, |neighbor| levels[*neighbor])
+        }
+    };
+    let mut sequences = vec![];
+
+    for run in &level_runs {
+        if is_matched_pdi[run[0]] {
+            continue;
+        }
+
+        let mut indices = run.clone();
+
+        while let Some(pdi) = matching_pdis[*indices.last().unwrap()] {
+            match run_starts.get(&pdi) {
+                Some(run_idx) => indices.extend(&level_runs[*run_idx]),
+                None => break,
+            }
+        }
+
+        let first = indices[0];
+        let last = *indices.last().unwrap();
+        let preceding_level = level_of_neighbor(first, -1);
+        let following_level = if is_isolate_initiator(classes[last]) {
+            paragraph_level
+        } else {
+            level_of_neighbor(last, 1)
+        };
+
+        sequences.push(IsolatingRunSequence {
+            sos: strong_class_of_level(levels[first].max(preceding_level)),
+            eos: strong_class_of_level(levels[last].max(following_level)),
+            indices,
+        });
+    }
+
+    sequences
+}
+
+/// Applies the rules W1 to W7.
+fn resolve_weak_types(classes: &mut [BidiClass], sos: BidiClass) {
+    // W1
+    for idx in 0..classes.len() {
+        if classes[idx] == BidiClass::Nsm {
+            classes[idx] = match idx.checked_sub(1).map(|previous| classes[previous]) {
+                None => sos,
+                Some(previous) if is_isolate_initiator(previous) || previous == BidiClass::Pdi => {
+                    BidiClass::On
+                }
+                Some(previous) => previous,
+            };
+        }
+    }
+
+    // W2 and W3
+    let mut last_strong_class = sos;
+
+    for bidi_class in classes.iter_mut() {
+        match bidi_class {
+            BidiClass::L | BidiClass::R => last_strong_class = *bidi_class,
+            BidiClass::Al => {
+                last_strong_class = BidiClass::Al;
+                *bidi_class = BidiClass::R;
+            }
+            BidiClass::En if last_strong_class == BidiClass::Al => *bidi_class = BidiClass::An,
+            _ => {}
+        }
+    }
+
+    // W4
+    for idx in 1..classes.len().saturating_sub(1) {
+        let (previous, next) = (classes[idx - 1], classes[idx + 1]);
+
+        match (previous, classes[idx], next) {
+            (BidiClass::En, BidiClass::Es, BidiClass::En)
+            | (BidiClass::En, BidiClass::Cs, BidiClass::En) => classes[idx] = BidiClass::En,
+            (BidiClass::An, BidiClass::Cs, BidiClass::An) => classes[idx] = BidiClass::An,
+            _ => {}
+        }
+    }
+
+    // W5
+    let mut idx = 0;
+
+    while idx < classes.len() {
+        if classes[idx] != BidiClass::Et {
+            idx += 1;
+            continue;
+        }
+
+        let end = (idx..classes.len())
+            .find(|end| classes[*end] != BidiClass::Et)
+            .unwrap_or(classes.len());
+        let is_adjacent_to_number = (idx > 0 && classes[idx - 1] == BidiClass::En)
+            || classes.get(end) == Some(&BidiClass::En);
+
+        if is_adjacent_to_number {
+            for bidi_class in &mut classes[idx..end] {
+                *bidi_class = BidiClass::En;
+            }
+        }
+        idx = end;
+    }
+
+    // W6
+    for bidi_class in classes.iter_mut() {
+        if matches!(bidi_class, BidiClass::Es | BidiClass::Et | BidiClass::Cs) {
+            *bidi_class = BidiClass::On;
+        }
+    }
+
+    // W7
+    let mut last_strong_class = sos;
+
+    for bidi_class in classes.iter_mut() {
+        match bidi_class {
+            BidiClass::L | BidiClass::R => last_strong_class = *bidi_class,
+            BidiClass::En if last_strong_class == BidiClass::L => *bidi_class = BidiClass::L,
+            _ => {}
+        }
+    }
+}
+
+/// Applies the rules N1 and N2 to sequences of neutrals and isolate formatting characters.
+fn resolve_neutral_types(
+    classes: &mut [BidiClass],
+    sos: BidiClass,
+    eos: BidiClass,
+    embedding_direction: BidiClass,
+) {
+    let is_neutral = |bidi_class: BidiClass| {
+        matches!(
+            bidi_class,
+            BidiClass::B | BidiClass::S | BidiClass::Ws | BidiClass::On
+        ) || is_isolate_initiator(bidi_class)
+            || bidi_class == BidiClass::Pdi
+    };
+    let mut idx = 0;
+
+    while idx < classes.len() {
+        if !is_neutral(classes[idx]) {
+            idx += 1;
+            continue;
+        }
+
+        let end = (idx..classes.len())
+            .find(|end| !is_neutral(classes[*end]))
+            .unwrap_or(classes.len());
+        let preceding_direction = match idx {
+            0 => sos,
+            _ => strong_direction(classes[idx - 1]).unwrap_or(embedding_direction),
+        };
+        let following_direction = classes.get(end).map_or(eos, |bidi_class| {
+            strong_direction(*bidi_class).unwrap_or(embedding_direction)
+        });
+        let resolved_class = if preceding_direction == following_direction {
+            preceding_direction
+        } else {
+            embedding_direction
+        };
+
+        for bidi_class in &mut classes[idx..end] {
+            *bidi_class = resolved_class;
+        }
+        idx = end;
+    }
+}
+
+/// Resets segment separators, paragraph separators and any sequence of whitespace
+/// and isolate formatting characters before them or at the end of the line
+/// to the paragraph level as in rule L1.
+fn reset_whitespace_levels(
+    classes: &[BidiClass],
+    is_removed: &[bool],
+    levels: &mut [u8],
+    paragraph_level: u8,
+) {
+    let mut whitespace_start = None;
+
+    for (idx, bidi_class) in classes.iter().enumerate() {
+        match bidi_class {
+            BidiClass::S | BidiClass::B => {
+                for level in &mut levels[whitespace_start.unwrap_or(idx)..=idx] {
+                    *level = paragraph_level;
+                }
+                whitespace_start = None;
+            }
+            BidiClass::Ws | BidiClass::Lri | BidiClass::Rli | BidiClass::Fsi | BidiClass::Pdi => {
+                whitespace_start.get_or_insert(idx);
+            }
+            _ if is_removed[idx] => {
+                whitespace_start.get_or_insert(idx);
+            }
+            _ => whitespace_start = None,
+        }
+    }
+
+    if let Some(start) = whitespace_start {
+        for level in &mut levels[start..] {
+            *level = paragraph_level;
+        }
+    }
+}
+
+/// Reverses any sequence of characters at each level from the highest level
+/// down to the lowest odd level as in rule L2.
+fn reorder(levels: &[Option<u8>]) -> Vec<usize> {
+    let mut order = (0..levels.len())
+        .filter(|idx| levels[*idx].is_some())
+        .collect::<Vec<_>>();
+    let level_of = |idx: usize| levels[idx].unwrap();
+    let highest_level = order.iter().map(|idx| level_of(*idx)).max().unwrap_or(0);
+    let lowest_odd_level = order
+        .iter()
+        .map(|idx| level_of(*idx))
+        .filter(|level| *level % 2 == 1)
+        .min()
+        .unwrap_or(highest_level + 1);
+
+    for level in (lowest_odd_level..=highest_level).rev() {
+        let mut start = 0;
+
+        while start < order.len() {
+            if level_of(order[start]) < level {
+                start += 1;
+                continue;
+            }
+
+            let end = (start..order.len())
+                .find(|end| level_of(order[*end]) < level)
+                .unwrap_or(order.len());
+
+            order[start..end].reverse();
+            start = end;
+        }
+    }
+
+    order
+}
+
+fn find_level_runs(levels: &[Option<u8>]) -> Vec<LevelRun> {
+    let mut runs: Vec<LevelRun> = vec![];
+
+    for (idx, level) in levels.iter().enumerate() {
+        let level = match level {
+            Some(level) => *level,
+            None => continue,
+        };
+
+        match runs.last_mut() {
+            Some(run) if run.level == level => run.end = idx,
+            _ => runs.push(LevelRun {
+                start: idx,
+                end: idx,
+                level,
+            }),
+        }
+    }
+
+    runs
+}
@@ -0,0 +1,92 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use strum_macros::{AsRefStr, EnumIter, EnumString};
+
+#[derive(AsRefStr, EnumIter, EnumString, Clone, Copy, PartialEq)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum BidiClass {
+    L,
+    R,
+    Al,
+    En,
+    Es,
+    Et,
+    An,
+    Cs,
+    Nsm,
+    Bn,
+    B,
+    S,
+    Ws,
+    On,
+    Lre,
+    Lro,
+    Rle,
+    Rlo,
+    Pdf,
+    Lri,
+    Rli,
+    Fsi,
+    Pdi,
+}
+
+impl BidiClass {
+    pub fn description(&self) -> &'static str {
+        match self {
+            BidiClass::L => "Left To Right",
+            BidiClass::R => "Right To Left",
+            BidiClass::Al => "Arabic Letter",
+            BidiClass::En => "European Number",
+            BidiClass::Es => "European Separator",
+            BidiClass::Et => "European Terminator",
+            BidiClass::An => "Arabic Number",
+            BidiClass::Cs => "Common Separator",
+            BidiClass::Nsm => "Nonspacing Mark",
+            BidiClass::Bn => "Boundary Neutral",
+            BidiClass::B => "Paragraph Separator",
+            BidiClass::S => "Segment Separator",
+            BidiClass::Ws => "White Space",
+            BidiClass::On => "Other Neutral",
+            BidiClass::Lre => "Left To Right Embedding",
+            BidiClass::Lro => "Left To Right Override",
+            BidiClass::Rle => "Right To Left Embedding",
+            BidiClass::Rlo => "Right To Left Override",
+            BidiClass::Pdf => "Pop Directional Format",
+            BidiClass::Lri => "Left To Right Isolate",
+            BidiClass::Rli => "Right To Left Isolate",
+            BidiClass::Fsi => "First Strong Isolate",
+            BidiClass::Pdi => "Pop Directional Isolate",
+        }
+    }
+
+    /// Returns the type of the class as in table 4 of UAX #9,
+    /// which is `strong`, `weak`, `neutral` or `explicit formatting`.
+    pub fn class_type(&self) -> &'static str {
+        match self {
+            BidiClass::L | BidiClass::R | BidiClass::Al => "strong",
+            BidiClass::En
+            | BidiClass::Es
+            | BidiClass::Et
+            | BidiClass::An
+            | BidiClass::Cs
+            | BidiClass::Nsm
+            | BidiClass::Bn => "weak",
+            BidiClass::B | BidiClass::S | BidiClass::Ws | BidiClass::On => "neutral",
+            _ => "explicit formatting",
+        }
+    }
+}
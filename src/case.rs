@@ -0,0 +1,411 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use rusqlite::{Connection, Row, NO_PARAMS};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+const COMBINING_DOT_ABOVE: u32 = 0x0307;
+const LATIN_CAPITAL_LETTER_I: u32 = 0x0049;
+const ABOVE_COMBINING_CLASS: u8 = 230;
+
+#[derive(Clone, Copy)]
+pub enum CaseConversion {
+    Upper,
+    Lower,
+    Title,
+    Fold,
+}
+
+impl FromStr for CaseConversion {
+    type Err = String;
+
+    fn from_str(conversion: &str) -> Result<Self, Self::Err> {
+        match conversion.to_ascii_lowercase().as_str() {
+            "upper" => Ok(CaseConversion::Upper),
+            "lower" => Ok(CaseConversion::Lower),
+            "title" => Ok(CaseConversion::Title),
+            "fold" => Ok(CaseConversion::Fold),
+            _ => Err(format!(
+                "unknown case '{}', expected upper, lower, title or fold",
+                conversion
+            )),
+        }
+    }
+}
+
+/// The kinds of case mappings, named as the columns of the database.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum MappingType {
+    Uppercase,
+    Lowercase,
+    Titlecase,
+    CaseFolding,
+}
+
+impl MappingType {
+    const ALL: [MappingType; 4] = [
+        MappingType::Uppercase,
+        MappingType::Lowercase,
+        MappingType::Titlecase,
+        MappingType::CaseFolding,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            MappingType::Uppercase => "uppercase",
+            MappingType::Lowercase => "lowercase",
+            MappingType::Titlecase => "titlecase",
+            MappingType::CaseFolding => "case_folding",
+        }
+    }
+
+    fn parse(mapping_type: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|candidate| candidate.as_str() == mapping_type)
+            .copied()
+    }
+}
+
+/// Accepts a language tag such as `tr`, `lt` or `az-Latn` and returns
+/// its lowercase language code, which selects the tailored case mappings.
+pub fn parse_language(language: &str) -> Result<String, String> {
+    let language_code = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    if (2..=3).contains(&language_code.len())
+        && language_code.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        Ok(language_code)
+    } else {
+        Err(format!(
+            "'{}' is not a language code such as tr, az or lt",
+            language
+        ))
+    }
+}
+
+/// The simple and the full mapping of a character to one case, each written
+/// as the resulting text. A mapping is none if it maps the character to itself.
+#[derive(Clone, Default)]
+pub struct CaseMapping {
+    pub simple: Option<String>,
+    pub full: Option<String>,
+}
+
+impl CaseMapping {
+    /// Reads the columns `simple_<mapping_type>` and `full_<mapping_type>`
+    /// for a mapping type such as `uppercase` or `case_folding`.
+    pub fn from_row(row: &Row, mapping_type: &str) -> Self {
+        let read_mapping = |column: String| {
+            row.get_unwrap::<_, Option<String>>(row.column_index(&column).unwrap())
+                .map(|mapping| to_text(&parse_mapping(&mapping)))
+        };
+
+        Self {
+            simple: read_mapping(format!("simple_{}", mapping_type)),
+            full: read_mapping(format!("full_{}", mapping_type)),
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.simple.is_none() && self.full.is_none()
+    }
+}
+
+/// A case mapping from SpecialCasing.txt or CaseFolding.txt that only applies
+/// to a language, within a context such as `Final_Sigma` or both.
+struct ConditionalMapping {
+    mapping_type: MappingType,
+    mapping: Vec<u32>,
+    language: Option<String>,
+    context: Option<String>,
+}
+
+/// Converts text with the full case mappings of the database as described
+/// in section 3.13 of the Unicode Standard, including the conditional mappings
+/// that depend on the language and on the characters around each character.
+pub struct CaseConverter {
+    mappings: HashMap<(u32, MappingType), Vec<u32>>,
+    conditional_mappings: HashMap<u32, Vec<ConditionalMapping>>,
+    cased_codepoints: HashSet<u32>,
+    case_ignorable_codepoints: HashSet<u32>,
+    soft_dotted_codepoints: HashSet<u32>,
+    combining_classes: HashMap<u32, u8>,
+}
+
+impl CaseConverter {
+    pub fn load(database: &Connection) -> Self {
+        let mut converter = Self {
+            mappings: HashMap::new(),
+            conditional_mappings: HashMap::new(),
+            cased_codepoints: HashSet::new(),
+            case_ignorable_codepoints: HashSet::new(),
+            soft_dotted_codepoints: HashSet::new(),
+            combining_classes: HashMap::new(),
+        };
+        let mut statement = database
+            .prepare(
+                "SELECT codepoint, combining_class, is_cased, is_case_ignorable, is_soft_dotted, \
+                 full_uppercase, full_lowercase, full_titlecase, full_case_folding \
+                 FROM UnicodeData WHERE combining_class != 0 OR is_cased OR is_case_ignorable \
+                 OR full_uppercase IS NOT NULL OR full_lowercase IS NOT NULL \
+                 OR full_titlecase IS NOT NULL OR full_case_folding IS NOT NULL",
+            )
+            .unwrap();
+        let mut rows = statement.query(NO_PARAMS).unwrap();
+
+        while let Some(row) = rows.next().unwrap() {
+            let codepoint: u32 = row.get_unwrap(0);
+            let combining_class: u8 = row.get_unwrap(1);
+
+            if combining_class != 0 {
+                converter
+                    .combining_classes
+                    .insert(codepoint, combining_class);
+            }
+            if row.get_unwrap(2) {
+                converter.cased_codepoints.insert(codepoint);
+            }
+            if row.get_unwrap(3) {
+                converter.case_ignorable_codepoints.insert(codepoint);
+            }
+            if row.get_unwrap(4) {
+                converter.soft_dotted_codepoints.insert(codepoint);
+            }
+
+            for (idx, mapping_type) in MappingType::ALL.iter().enumerate() {
+                if let Some(mapping) = row.get_unwrap::<_, Option<String>>(5 + idx) {
+                    converter
+                        .mappings
+                        .insert((codepoint, *mapping_type), parse_mapping(&mapping));
+                }
+            }
+        }
+
+        let mut statement = database
+            .prepare(
+                "SELECT codepoint, type, mapping, language, context FROM ConditionalCaseMappings",
+            )
+            .unwrap();
+        let mut rows = statement.query(NO_PARAMS).unwrap();
+
+        while let Some(row) = rows.next().unwrap() {
+            let mapping_type: String = row.get_unwrap(1);
+            let mapping: String = row.get_unwrap(2);
+
+            converter
+                .conditional_mappings
+                .entry(row.get_unwrap(0))
+                .or_default()
+                .push(ConditionalMapping {
+                    mapping_type: MappingType::parse(&mapping_type)
+                        .expect("Unknown case mapping type in database"),
+                    mapping: parse_mapping(&mapping),
+                    language: row.get_unwrap(3),
+                    context: row.get_unwrap(4),
+                });
+        }
+
+        converter
+    }
+
+    /// Converts text to the given case. Title case maps the first cased character
+    /// of each word as determined by UAX #29 to titlecase and the others to lowercase.
+    pub fn convert(
+        &self,
+        text: &str,
+        conversion: CaseConversion,
+        language: Option<&str>,
+    ) -> String {
+        let codepoints = text.chars().map(|c| c as u32).collect::<Vec<_>>();
+        let mut titlecase_positions = HashSet::new();
+
+        if let CaseConversion::Title = conversion {
+            let mut position = 0;
+
+            for word in text.split_word_bounds() {
+                if let Some(offset) = word
+                    .chars()
+                    .position(|c| self.cased_codepoints.contains(&(c as u32)))
+                {
+                    titlecase_positions.insert(position + offset);
+                }
+                position += word.chars().count();
+            }
+        }
+
+        let mut converted_text = String::new();
+
+        for idx in 0..codepoints.len() {
+            let mapping_type = match conversion {
+                CaseConversion::Upper => MappingType::Uppercase,
+                CaseConversion::Lower => MappingType::Lowercase,
+                CaseConversion::Title if titlecase_positions.contains(&idx) => {
+                    MappingType::Titlecase
+                }
+                CaseConversion::Title => MappingType::Lowercase,
+                CaseConversion::Fold => MappingType::CaseFolding,
+            };
+
+            converted_text.push_str(&to_text(&self.map(
+                &codepoints,
+                idx,
+                mapping_type,
+                language,
+            )));
+        }
+
+        converted_text
+    }
+
+    /// Maps the code point at the given position, preferring the mappings tailored
+    /// to the language over the ones for all languages and those over the full mapping.
+    fn map(
+        &self,
+        codepoints: &[u32],
+        idx: usize,
+        mapping_type: MappingType,
+        language: Option<&str>,
+    ) -> Vec<u32> {
+        let codepoint = codepoints[idx];
+        let conditional_mappings = self
+            .conditional_mappings
+            .get(&codepoint)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let applicable_mappings = conditional_mappings
+            .iter()
+            .filter(|mapping| {
+                mapping.mapping_type == mapping_type
+                    && language.is_some()
+                    && mapping.language.as_deref() == language
+            })
+            .chain(conditional_mappings.iter().filter(|mapping| {
+                mapping.mapping_type == mapping_type && mapping.language.is_none()
+            }));
+
+        for conditional_mapping in applicable_mappings {
+            let is_in_context = match &conditional_mapping.context {
+                Some(context) => self.is_in_context(codepoints, idx, context),
+                None => true,
+            };
+
+            if is_in_context {
+                return conditional_mapping.mapping.clone();
+            }
+        }
+
+        self.mappings
+            .get(&(codepoint, mapping_type))
+            .cloned()
+            .unwrap_or_else(|| vec![codepoint])
+    }
+
+    /// Evaluates the casing contexts of table 3-17 of the Unicode Standard,
+    /// any of which can be negated with the prefix `Not_`.
+    fn is_in_context(&self, codepoints: &[u32], idx: usize, context: &str) -> bool {
+        if let Some(context) = context.strip_prefix("Not_") {
+            return !self.is_in_context(codepoints, idx, context);
+        }
+
+        let preceding = codepoints[..idx].iter().rev().copied();
+        let following = codepoints[idx + 1..].iter().copied();
+
+        match context {
+            "Final_Sigma" => {
+                self.is_next_to_cased_letter(preceding) && !self.is_next_to_cased_letter(following)
+            }
+            "After_Soft_Dotted" => self.is_within_combining_sequence(preceding, |codepoint| {
+                self.soft_dotted_codepoints.contains(&codepoint)
+            }),
+            "More_Above" => self.is_within_combining_sequence(following, |codepoint| {
+                self.combining_class(codepoint) == ABOVE_COMBINING_CLASS
+            }),
+            "Before_Dot" => self.is_within_combining_sequence(following, |codepoint| {
+                codepoint == COMBINING_DOT_ABOVE
+            }),
+            "After_I" => self.is_within_combining_sequence(preceding, |codepoint| {
+                codepoint == LATIN_CAPITAL_LETTER_I
+            }),
+            _ => false,
+        }
+    }
+
+    /// Checks whether a cased letter comes first in the given direction
+    /// once case-ignorable characters are skipped.
+    fn is_next_to_cased_letter(&self, codepoints: impl Iterator<Item = u32>) -> bool {
+        for codepoint in codepoints {
+            if self.cased_codepoints.contains(&codepoint) {
+                return true;
+            }
+            if !self.case_ignorable_codepoints.contains(&codepoint) {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    /// Checks whether a code point satisfying the predicate comes in the given direction
+    /// before any character of combining class 0 or 230 that does not satisfy it.
+    fn is_within_combining_sequence(
+        &self,
+        codepoints: impl Iterator<Item = u32>,
+        predicate: impl Fn(u32) -> bool,
+    ) -> bool {
+        for codepoint in codepoints {
+            if predicate(codepoint) {
+                return true;
+            }
+
+            let combining_class = self.combining_class(codepoint);
+
+            if combining_class == 0 || combining_class == ABOVE_COMBINING_CLASS {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    fn combining_class(&self, codepoint: u32) -> u8 {
+        self.combining_classes
+            .get(&codepoint)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Parses a case mapping such as `0053 0053`, an empty one removes the character.
+fn parse_mapping(mapping: &str) -> Vec<u32> {
+    mapping
+        .split_whitespace()
+        .map(|hexcode| u32::from_str_radix(hexcode, 16).expect("Invalid case mapping in database"))
+        .collect()
+}
+
+fn to_text(codepoints: &[u32]) -> String {
+    codepoints
+        .iter()
+        .filter_map(|codepoint| char::from_u32(*codepoint))
+        .collect()
+}
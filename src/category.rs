@@ -14,9 +14,9 @@
  * limitations under the License.
  */
 
-use strum_macros::EnumString;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
 
-#[derive(EnumString)]
+#[derive(AsRefStr, EnumIter, EnumString)]
 pub enum Category {
     Lu,
     Ll,
@@ -0,0 +1,206 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bidi_class::BidiClass;
+use crate::case::CaseMapping;
+use crate::category::Category;
+use crate::decomposition::Decomposition;
+use crate::numeric::NumericType;
+use rusqlite::Row;
+use std::char;
+use std::str::FromStr;
+
+pub const CHARACTER_COLUMNS: &str = "codepoint, name, category, block, age, \
+    unicode_1_name, iso_comment, combining_class, decomposition, \
+    simple_uppercase, full_uppercase, simple_lowercase, full_lowercase, \
+    simple_titlecase, full_titlecase, simple_case_folding, full_case_folding, \
+    bidi_class, is_bidi_mirrored, bidi_mirroring_glyph, bidi_paired_bracket, \
+    bidi_paired_bracket_type, numeric_type, numeric_value, \
+    (SELECT group_concat(type || ':' || alias, ';') FROM NameAliases \
+     WHERE NameAliases.codepoint = UnicodeData.codepoint) AS aliases";
+
+#[derive(Clone)]
+pub struct UnicodeCharacter {
+    pub codepoint: u32,
+    pub name: String,
+    pub category: String,
+    pub block: String,
+    pub age: String,
+    pub aliases: Vec<NameAlias>,
+    pub unicode_1_name: Option<String>,
+    pub iso_comment: Option<String>,
+    pub combining_class: u8,
+    pub decomposition_mapping: Option<String>,
+    pub decomposition: Option<Decomposition>,
+    pub uppercase: CaseMapping,
+    pub lowercase: CaseMapping,
+    pub titlecase: CaseMapping,
+    pub case_folding: CaseMapping,
+    pub bidi_class: String,
+    pub is_bidi_mirrored: bool,
+    pub bidi_mirroring_glyph: Option<u32>,
+    pub bidi_paired_bracket: Option<u32>,
+    pub bidi_paired_bracket_type: Option<String>,
+    pub numeric_type: Option<String>,
+    pub numeric_value: Option<String>,
+    pub annotations: Option<Vec<Annotation>>,
+    pub decomposition_group: Option<String>,
+    pub score: Option<u32>,
+}
+
+#[derive(Clone)]
+pub struct NameAlias {
+    pub alias: String,
+    pub alias_type: String,
+}
+
+/// An annotation from NamesList.txt. The text of a cross reference
+/// is the name of the referenced character.
+#[derive(Clone)]
+pub struct Annotation {
+    pub annotation_type: String,
+    pub text: String,
+    pub reference: Option<u32>,
+}
+
+impl UnicodeCharacter {
+    pub fn from_row(row: &Row) -> Self {
+        Self {
+            codepoint: row.get_unwrap(row.column_index("codepoint").unwrap()),
+            name: row.get_unwrap(row.column_index("name").unwrap()),
+            category: row.get_unwrap(row.column_index("category").unwrap()),
+            block: row.get_unwrap(row.column_index("block").unwrap()),
+            age: row.get_unwrap(row.column_index("age").unwrap()),
+            aliases: row
+                .get_unwrap::<_, Option<String>>(row.column_index("aliases").unwrap())
+                .map(|aliases| parse_aliases(&aliases))
+                .unwrap_or_default(),
+            unicode_1_name: row.get_unwrap(row.column_index("unicode_1_name").unwrap()),
+            iso_comment: row.get_unwrap(row.column_index("iso_comment").unwrap()),
+            combining_class: row.get_unwrap(row.column_index("combining_class").unwrap()),
+            decomposition_mapping: row.get_unwrap(row.column_index("decomposition").unwrap()),
+            decomposition: None,
+            uppercase: CaseMapping::from_row(row, "uppercase"),
+            lowercase: CaseMapping::from_row(row, "lowercase"),
+            titlecase: CaseMapping::from_row(row, "titlecase"),
+            case_folding: CaseMapping::from_row(row, "case_folding"),
+            bidi_class: row.get_unwrap(row.column_index("bidi_class").unwrap()),
+            is_bidi_mirrored: row.get_unwrap(row.column_index("is_bidi_mirrored").unwrap()),
+            bidi_mirroring_glyph: row.get_unwrap(row.column_index("bidi_mirroring_glyph").unwrap()),
+            bidi_paired_bracket: row.get_unwrap(row.column_index("bidi_paired_bracket").unwrap()),
+            bidi_paired_bracket_type: row
+                .get_unwrap(row.column_index("bidi_paired_bracket_type").unwrap()),
+            numeric_type: row.get_unwrap(row.column_index("numeric_type").unwrap()),
+            numeric_value: row.get_unwrap(row.column_index("numeric_value").unwrap()),
+            annotations: None,
+            decomposition_group: None,
+            score: None,
+        }
+    }
+
+    pub fn character(&self) -> Option<char> {
+        char::from_u32(self.codepoint)
+    }
+
+    /// Surrogate code points cannot be represented as `char`,
+    /// so their glyph is left empty.
+    pub fn glyph(&self) -> String {
+        self.character().map(String::from).unwrap_or_default()
+    }
+
+    pub fn hex_code(&self) -> String {
+        format!("U+{:04X}", self.codepoint)
+    }
+
+    pub fn category_description(&self) -> &'static str {
+        Category::from_str(&self.category).unwrap().description()
+    }
+
+    pub fn bidi_class(&self) -> BidiClass {
+        BidiClass::from_str(&self.bidi_class).unwrap()
+    }
+
+    pub fn numeric_type(&self) -> Option<NumericType> {
+        self.numeric_type
+            .as_ref()
+            .map(|numeric_type| NumericType::from_str(numeric_type).unwrap())
+    }
+}
+
+/// Parses aliases concatenated as `type:alias` and separated by semicolons.
+fn parse_aliases(aliases: &str) -> Vec<NameAlias> {
+    aliases
+        .split(';')
+        .filter_map(|alias| {
+            let (alias_type, alias) = alias.split_once(':')?;
+            Some(NameAlias {
+                alias: alias.to_string(),
+                alias_type: alias_type.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub struct GraphemeCluster {
+    pub text: String,
+    pub characters: Vec<UnicodeCharacter>,
+}
+
+pub enum SearchResults {
+    Characters(Vec<UnicodeCharacter>),
+    GraphemeClusters(Vec<GraphemeCluster>),
+}
+
+impl SearchResults {
+    pub fn len(&self) -> usize {
+        match self {
+            SearchResults::Characters(characters) => characters.len(),
+            SearchResults::GraphemeClusters(grapheme_clusters) => grapheme_clusters.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn characters_mut(&mut self) -> Vec<&mut UnicodeCharacter> {
+        match self {
+            SearchResults::Characters(characters) => characters.iter_mut().collect(),
+            SearchResults::GraphemeClusters(grapheme_clusters) => grapheme_clusters
+                .iter_mut()
+                .flat_map(|grapheme_cluster| grapheme_cluster.characters.iter_mut())
+                .collect(),
+        }
+    }
+
+    /// Skips the first `offset` results and keeps at most `limit` of the remaining ones.
+    pub fn paginate(self, offset: usize, limit: Option<usize>) -> Self {
+        let limit = limit.unwrap_or(usize::MAX);
+
+        match self {
+            SearchResults::Characters(characters) => {
+                SearchResults::Characters(characters.into_iter().skip(offset).take(limit).collect())
+            }
+            SearchResults::GraphemeClusters(grapheme_clusters) => SearchResults::GraphemeClusters(
+                grapheme_clusters
+                    .into_iter()
+                    .skip(offset)
+                    .take(limit)
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::character::UnicodeCharacter;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+
+const CANONICAL_GROUP: &str = "canonical";
+
+// The tags of compatibility decompositions in the order of section 5.7.3 of UAX #44.
+const COMPATIBILITY_TAGS: [&str; 16] = [
+    "font", "noBreak", "initial", "medial", "final", "isolated", "circle", "super", "sub",
+    "vertical", "wide", "narrow", "small", "square", "fraction", "compat",
+];
+
+/// The decomposition mapping of a character from UnicodeData.txt,
+/// expanded recursively so that each component has its own decomposition.
+#[derive(Clone)]
+pub struct Decomposition {
+    /// The formatting tag of a compatibility decomposition such as `font` or `compat`,
+    /// canonical decompositions have none.
+    pub tag: Option<String>,
+    pub components: Vec<DecompositionComponent>,
+}
+
+#[derive(Clone)]
+pub struct DecompositionComponent {
+    pub codepoint: u32,
+    pub name: String,
+    pub decomposition: Option<Decomposition>,
+}
+
+impl Decomposition {
+    /// Expands a decomposition mapping such as `<noBreak> 0020` or `0041 0300`
+    /// by looking up the names and decompositions of its components.
+    pub fn expand(mapping: &str, database: &Connection) -> Self {
+        let (tag, codepoints) = parse_decomposition_mapping(mapping);
+        let components = codepoints
+            .into_iter()
+            .map(|codepoint| {
+                let (name, mapping) = database
+                    .query_row(
+                        "SELECT name, decomposition FROM UnicodeData WHERE codepoint = ?",
+                        [codepoint],
+                        |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+                    )
+                    .optional()
+                    .expect("Decomposition could not be retrieved from database")
+                    .unwrap_or_else(|| (format!("<reserved-{:04X}>", codepoint), None));
+
+                DecompositionComponent {
+                    codepoint,
+                    name,
+                    decomposition: mapping.map(|mapping| Decomposition::expand(&mapping, database)),
+                }
+            })
+            .collect();
+
+        Self { tag, components }
+    }
+
+    /// Returns `canonical` or the tag of a compatibility decomposition.
+    pub fn decomposition_type(&self) -> &str {
+        self.tag.as_deref().unwrap_or("canonical")
+    }
+}
+
+/// Splits a decomposition mapping into its optional tag without angle brackets
+/// and the code points it maps to.
+pub fn parse_decomposition_mapping(mapping: &str) -> (Option<String>, Vec<u32>) {
+    let mut tag = None;
+    let mut codepoints = vec![];
+
+    for part in mapping.split_whitespace() {
+        if let Some(tag_name) = part.strip_prefix('<').and_then(|p| p.strip_suffix('>')) {
+            tag = Some(tag_name.to_string());
+        } else if let Ok(codepoint) = u32::from_str_radix(part, 16) {
+            codepoints.push(codepoint);
+        }
+    }
+
+    (tag, codepoints)
+}
+
+/// Returns the property value alias of a canonical combining class
+/// as listed in PropertyValueAliases.txt, with underscores replaced by spaces.
+/// The classes from 10 to 199, whose aliases are merely their numbers,
+/// are fixed position classes.
+pub fn combining_class_name(combining_class: u8) -> Option<&'static str> {
+    match combining_class {
+        0 => Some("Not Reordered"),
+        1 => Some("Overlay"),
+        6 => Some("Han Reading"),
+        7 => Some("Nukta"),
+        8 => Some("Kana Voicing"),
+        9 => Some("Virama"),
+        10..=199 => Some("Fixed Position"),
+        200 => Some("Attached Below Left"),
+        202 => Some("Attached Below"),
+        214 => Some("Attached Above"),
+        216 => Some("Attached Above Right"),
+        218 => Some("Below Left"),
+        220 => Some("Below"),
+        222 => Some("Below Right"),
+        224 => Some("Left"),
+        226 => Some("Right"),
+        228 => Some("Above Left"),
+        230 => Some("Above"),
+        232 => Some("Above Right"),
+        233 => Some("Double Below"),
+        234 => Some("Double Above"),
+        240 => Some("Iota Subscript"),
+        _ => None,
+    }
+}
+
+/// Finds the characters whose full decomposition contains or equals the
+/// full decomposition of the given base string, such as é, ẽ, ｅ or ⓔ for e.
+/// The base itself is not included.
+///
+/// Characters whose canonical decomposition contains the base are grouped as
+/// `canonical`. The others are grouped by the first compatibility tag met
+/// while decomposing them, so ẛ is grouped as `compat` for the base s even
+/// though its own decomposition is a canonical one. The groups are ordered
+/// like the tags in UAX #44, the characters within them by code point.
+pub fn find_variants(
+    base: &str,
+    characters: Vec<UnicodeCharacter>,
+    mappings: &HashMap<u32, String>,
+) -> Vec<UnicodeCharacter> {
+    let base_codepoints = base.chars().map(|c| c as u32).collect::<Vec<_>>();
+    let canonical_base = decompose_fully(&base_codepoints, mappings, false).0;
+    let compatibility_base = decompose_fully(&base_codepoints, mappings, true).0;
+
+    let mut variants = characters
+        .into_iter()
+        .filter(|character| base_codepoints != [character.codepoint])
+        .filter_map(|mut character| {
+            let codepoints = [character.codepoint];
+            let canonical_decomposition = decompose_fully(&codepoints, mappings, false).0;
+            let (compatibility_decomposition, tag) = decompose_fully(&codepoints, mappings, true);
+
+            character.decomposition_group =
+                if contains_sequence(&canonical_decomposition, &canonical_base) {
+                    Some(CANONICAL_GROUP.to_string())
+                } else if contains_sequence(&compatibility_decomposition, &compatibility_base) {
+                    tag.or_else(|| Some(CANONICAL_GROUP.to_string()))
+                } else {
+                    None
+                };
+
+            character.decomposition_group.as_ref()?;
+            Some(character)
+        })
+        .collect::<Vec<_>>();
+
+    variants.sort_by_key(|character| {
+        let group = character.decomposition_group.as_deref().unwrap_or_default();
+        let group_position = COMPATIBILITY_TAGS
+            .iter()
+            .position(|tag| *tag == group)
+            .map_or(0, |position| position + 1);
+
+        (group_position, character.codepoint)
+    });
+
+    variants
+}
+
+/// Decomposes code points recursively, either by canonical mappings only or by
+/// compatibility mappings as well. In the latter case, the first compatibility
+/// tag that is met is returned along with the decomposition.
+fn decompose_fully(
+    codepoints: &[u32],
+    mappings: &HashMap<u32, String>,
+    is_compatibility_allowed: bool,
+) -> (Vec<u32>, Option<String>) {
+    let mut decomposition = vec![];
+    let mut first_tag = None;
+
+    for codepoint in codepoints {
+        let mapping = mappings
+            .get(codepoint)
+            .map(|mapping| parse_decomposition_mapping(mapping))
+            .filter(|(tag, _)| tag.is_none() || is_compatibility_allowed);
+
+        match mapping {
+            Some((tag, mapped_codepoints)) => {
+                let (mapped_decomposition, nested_tag) =
+                    decompose_fully(&mapped_codepoints, mappings, is_compatibility_allowed);
+
+                if first_tag.is_none() {
+                    first_tag = tag.or(nested_tag);
+                }
+                decomposition.extend(mapped_decomposition);
+            }
+            None => decomposition.push(*codepoint),
+        }
+    }
+
+    (decomposition, first_tag)
+}
+
+fn contains_sequence(codepoints: &[u32], sequence: &[u32]) -> bool {
+    !sequence.is_empty()
+        && codepoints
+            .windows(sequence.len())
+            .any(|window| window == sequence)
+}
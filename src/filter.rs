@@ -0,0 +1,338 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bidi_class::BidiClass;
+use crate::category::Category;
+use rusqlite::{Connection, NO_PARAMS};
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+
+// Major classes of general categories that can be used instead of single categories.
+const MAJOR_CLASSES: [(&str, &str, &[&str]); 8] = [
+    ("L", "Letter", &["Lu", "Ll", "Lt", "Lm", "Lo"]),
+    ("LC", "Cased Letter", &["Lu", "Ll", "Lt"]),
+    ("M", "Mark", &["Mn", "Mc", "Me"]),
+    ("N", "Number", &["Nd", "Nl", "No"]),
+    (
+        "P",
+        "Punctuation",
+        &["Pc", "Pd", "Ps", "Pe", "Pi", "Pf", "Po"],
+    ),
+    ("S", "Symbol", &["Sm", "Sc", "Sk", "So"]),
+    ("Z", "Separator", &["Zs", "Zl", "Zp"]),
+    ("C", "Other", &["Cc", "Cf", "Cs", "Co", "Cn"]),
+];
+
+// Turns an age such as '13.0' into a number that sorts like the version.
+const AGE_NUMBER_SQL: &str = "(CAST(SUBSTR(age, 1, INSTR(age, '.') - 1) AS INTEGER) * 1000 \
+                              + CAST(SUBSTR(age, INSTR(age, '.') + 1) AS INTEGER))";
+
+/// A comma-separated list of general categories such as `Lu,Ll`, `L` or `Uppercase Letter`.
+pub struct CategoryFilter {
+    categories: Vec<String>,
+    is_unassigned_named: bool,
+}
+
+/// A comma-separated list of bidi classes such as `R,AL`, `Right To Left`
+/// or one of the types `strong`, `weak`, `neutral` or `explicit formatting`.
+pub struct BidiClassFilter {
+    bidi_classes: Vec<String>,
+}
+
+/// A comma-separated list of block names such as `Latin Extended-A`,
+/// compared while ignoring case, spaces, hyphens and underscores.
+pub struct BlockFilter {
+    blocks: Vec<String>,
+}
+
+/// A Unicode version or a range of versions that characters were added in,
+/// such as `13.0`, `>=6.0`, `<3.0` or `6.0..8.0`.
+pub struct AgeFilter {
+    min: Option<u32>,
+    max: Option<u32>,
+}
+
+impl CategoryFilter {
+    pub fn to_sql(&self, params: &mut Vec<String>) -> String {
+        params.extend(self.categories.iter().cloned());
+        format!("category IN ({})", placeholders(self.categories.len()))
+    }
+
+    /// Rejects the category Cn when named explicitly, as the filter could never match it.
+    /// Its code points can only be listed as ranges of a set such as `\p{Cn}`.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.is_unassigned_named {
+            Err(String::from(
+                "unassigned code points of category Cn are not stored in the database, \
+                 use --set '\\p{Cn}' --ranges to list them",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns whether the filter includes the unassigned code points of category Cn,
+    /// which are not stored in the database.
+    pub fn includes_unassigned(&self) -> bool {
+        self.categories.iter().any(|category| category == "Cn")
+    }
+}
+
+impl BidiClassFilter {
+    pub fn to_sql(&self, params: &mut Vec<String>) -> String {
+        params.extend(self.bidi_classes.iter().cloned());
+        format!("bidi_class IN ({})", placeholders(self.bidi_classes.len()))
+    }
+}
+
+impl BlockFilter {
+    pub fn to_sql(&self, params: &mut Vec<String>) -> String {
+        params.extend(self.blocks.iter().map(|block| normalize_name(block)));
+        format!(
+            "REPLACE(REPLACE(REPLACE(LOWER(block), ' ', ''), '-', ''), '_', '') IN ({})",
+            placeholders(self.blocks.len())
+        )
+    }
+
+    /// Checks that all block names of the filter exist in the database.
+    pub fn validate(&self, database: &Connection) -> Result<(), String> {
+        let mut statement = database
+            .prepare("SELECT DISTINCT block FROM UnicodeData")
+            .expect("SQL statement could not be prepared");
+        let known_blocks = statement
+            .query_map(NO_PARAMS, |row| row.get::<_, String>(0))
+            .expect("SQL query failed")
+            .map(|block| normalize_name(&block.expect("Block could not be retrieved")))
+            .collect::<Vec<_>>();
+        let unknown_blocks = self
+            .blocks
+            .iter()
+            .filter(|block| !known_blocks.contains(&normalize_name(block)))
+            .map(|block| block.as_str())
+            .collect::<Vec<_>>();
+
+        if unknown_blocks.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("unknown block '{}'", unknown_blocks.join("', '")))
+        }
+    }
+}
+
+impl AgeFilter {
+    pub fn to_sql(&self) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => format!("{} = {}", AGE_NUMBER_SQL, min),
+            (Some(min), Some(max)) => format!("{} BETWEEN {} AND {}", AGE_NUMBER_SQL, min, max),
+            (Some(min), None) => format!("{} >= {}", AGE_NUMBER_SQL, min),
+            (None, Some(max)) => format!("{} <= {}", AGE_NUMBER_SQL, max),
+            (None, None) => String::from("1"),
+        }
+    }
+}
+
+impl FromStr for CategoryFilter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let mut categories = vec![];
+        let mut is_unassigned_named = false;
+
+        for value in filter.split(',').map(str::trim) {
+            let value_categories = parse_categories(value)?;
+
+            is_unassigned_named |= value_categories == ["Cn"];
+
+            for category in value_categories {
+                if !categories.contains(&category) {
+                    categories.push(category);
+                }
+            }
+        }
+
+        Ok(CategoryFilter {
+            categories,
+            is_unassigned_named,
+        })
+    }
+}
+
+impl FromStr for BidiClassFilter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let mut bidi_classes = vec![];
+
+        for value in filter.split(',').map(str::trim) {
+            for bidi_class in parse_bidi_classes(value)? {
+                if !bidi_classes.contains(&bidi_class) {
+                    bidi_classes.push(bidi_class);
+                }
+            }
+        }
+
+        Ok(BidiClassFilter { bidi_classes })
+    }
+}
+
+impl FromStr for BlockFilter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let blocks = filter
+            .split(',')
+            .map(|block| block.trim().to_string())
+            .collect::<Vec<_>>();
+
+        if blocks.iter().any(|block| normalize_name(block).is_empty()) {
+            return Err(String::from("block name must not be empty"));
+        }
+
+        Ok(BlockFilter { blocks })
+    }
+}
+
+impl FromStr for AgeFilter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let filter = filter.trim();
+
+        if let Some(index) = filter.find("..") {
+            let (start, end) = (&filter[..index], &filter[index + 2..]);
+            let min = parse_optional_version(start)?;
+            let max = parse_optional_version(end)?;
+
+            if min.is_none() && max.is_none() {
+                return Err(String::from("age range must have a start or an end"));
+            }
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    return Err(format!(
+                        "age range '{}' must not start after its end",
+                        filter
+                    ));
+                }
+            }
+
+            return Ok(AgeFilter { min, max });
+        }
+
+        let (min, max) = if let Some(version) = filter.strip_prefix(">=") {
+            (Some(parse_version(version)?), None)
+        } else if let Some(version) = filter.strip_prefix("<=") {
+            (None, Some(parse_version(version)?))
+        } else if let Some(version) = filter.strip_prefix('>') {
+            (Some(parse_version(version)? + 1), None)
+        } else if let Some(version) = filter.strip_prefix('<') {
+            let version = parse_version(version)?;
+            if version == 0 {
+                return Err(String::from("age must not be less than 0.0"));
+            }
+            (None, Some(version - 1))
+        } else {
+            let version = parse_version(filter.strip_prefix('=').unwrap_or(filter))?;
+            (Some(version), Some(version))
+        };
+
+        Ok(AgeFilter { min, max })
+    }
+}
+
+fn parse_categories(value: &str) -> Result<Vec<String>, String> {
+    let normalized_value = normalize_name(value);
+
+    if let Some(category) = Category::iter().find(|category| {
+        category.as_ref().eq_ignore_ascii_case(value)
+            || normalize_name(category.description()) == normalized_value
+    }) {
+        return Ok(vec![category.as_ref().to_string()]);
+    }
+
+    if let Some((_, _, categories)) = MAJOR_CLASSES.iter().find(|(code, description, _)| {
+        code.eq_ignore_ascii_case(value) || normalize_name(description) == normalized_value
+    }) {
+        return Ok(categories
+            .iter()
+            .map(|category| category.to_string())
+            .collect());
+    }
+
+    Err(format!(
+        "unknown category '{}', expected a code such as Lu, a major class such as L \
+         or a description such as 'Uppercase Letter'",
+        value
+    ))
+}
+
+fn parse_bidi_classes(value: &str) -> Result<Vec<String>, String> {
+    let normalized_value = normalize_name(value);
+    let bidi_classes = BidiClass::iter()
+        .filter(|bidi_class| {
+            bidi_class.as_ref().eq_ignore_ascii_case(value)
+                || normalize_name(bidi_class.description()) == normalized_value
+                || normalize_name(bidi_class.class_type()) == normalized_value
+        })
+        .map(|bidi_class| bidi_class.as_ref().to_string())
+        .collect::<Vec<_>>();
+
+    if bidi_classes.is_empty() {
+        Err(format!(
+            "unknown bidi class '{}', expected a code such as AL, a description \
+             such as 'Arabic Letter' or a type such as strong or neutral",
+            value
+        ))
+    } else {
+        Ok(bidi_classes)
+    }
+}
+
+fn parse_optional_version(version: &str) -> Result<Option<u32>, String> {
+    if version.trim().is_empty() {
+        Ok(None)
+    } else {
+        parse_version(version).map(Some)
+    }
+}
+
+fn parse_version(version: &str) -> Result<u32, String> {
+    let version = version.trim();
+    let mut parts = version.splitn(2, '.');
+    let major = parts.next().and_then(|part| part.parse::<u32>().ok());
+    let minor = match parts.next() {
+        Some(part) => part.parse::<u32>().ok(),
+        None => Some(0),
+    };
+
+    match (major, minor) {
+        (Some(major), Some(minor)) if minor < 1000 => Ok(major * 1000 + minor),
+        _ => Err(format!(
+            "'{}' is not a Unicode version such as 13.0",
+            version
+        )),
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(",")
+}
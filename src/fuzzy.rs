@@ -0,0 +1,292 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::character::{UnicodeCharacter, CHARACTER_COLUMNS};
+use rusqlite::{params, Connection, NO_PARAMS};
+use std::cmp::{min, Reverse};
+use std::collections::HashMap;
+use std::iter;
+
+const EXACT_MATCH_SCORE: u32 = 100;
+const PREFIX_MATCH_SCORE: u32 = 80;
+const EDIT_DISTANCE_MATCH_SCORE: u32 = 60;
+const EDIT_DISTANCE_PENALTY: u32 = 10;
+const MAX_EXTRA_WORD_PENALTY: u32 = 9;
+
+/// Ranks characters by how well their names or formal aliases match the words of a query.
+///
+/// Each query word is scored against the best matching word of a name: exact
+/// matches score highest, followed by prefix matches and matches within a small
+/// edit distance. A name must match every query word to be included. Its score is
+/// the average of the word scores, lowered slightly for each name word that was not
+/// matched, so that shorter names rank first within the same kind of match.
+/// A character is ranked by the best scoring of its name and aliases.
+///
+/// Only characters that contain a matching word for every query word are loaded
+/// from the full-text index `NameIndex`, further restricted by the filter `conditions`
+/// with their `params`.
+pub fn rank_by_name(
+    database: &Connection,
+    query: &str,
+    conditions: &[String],
+    params: &[String],
+) -> Vec<UnicodeCharacter> {
+    let query_words = split_into_words(query);
+
+    if query_words.is_empty() {
+        return vec![];
+    }
+
+    let mut word_scores = HashMap::<(usize, String), Option<u32>>::new();
+    let candidate_query = match create_candidate_query(database, &query_words, &mut word_scores) {
+        Some(candidate_query) => candidate_query,
+        None => return vec![],
+    };
+
+    let mut sql = String::from(
+        "SELECT codepoint, name, \
+         (SELECT group_concat(alias, ';') FROM NameAliases \
+          WHERE NameAliases.codepoint = UnicodeData.codepoint) AS aliases \
+         FROM UnicodeData \
+         WHERE codepoint IN (SELECT rowid FROM NameIndex WHERE NameIndex MATCH ?)",
+    );
+    for condition in conditions {
+        sql.push_str(" AND ");
+        sql.push_str(condition);
+    }
+
+    let mut statement = database.prepare(&sql).unwrap();
+    let mut rows = statement
+        .query(iter::once(&candidate_query).chain(params))
+        .unwrap();
+    let mut ranked_codepoints = vec![];
+
+    while let Some(row) = rows.next().unwrap() {
+        let codepoint: u32 = row.get_unwrap(0);
+        let name: String = row.get_unwrap(1);
+        let aliases: Option<String> = row.get_unwrap(2);
+
+        let best_match = iter::once(name.as_str())
+            .chain(aliases.as_deref().unwrap_or_default().split(';'))
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| {
+                score_name(&query_words, name, &mut word_scores)
+                    .map(|score| (Reverse(score), name.len()))
+            })
+            .min();
+
+        if let Some((Reverse(score), name_length)) = best_match {
+            ranked_codepoints.push((codepoint, score, name_length));
+        }
+    }
+
+    ranked_codepoints
+        .sort_by_key(|&(codepoint, score, name_length)| (Reverse(score), name_length, codepoint));
+
+    retrieve_ranked_characters(database, ranked_codepoints)
+}
+
+/// Creates a full-text query on names and aliases that requires a match of each query word,
+/// or returns `None` if a query word matches no word at all. Exact and prefix matches
+/// are looked up as prefix terms, misspelled words are looked up in `NameWords`.
+fn create_candidate_query(
+    database: &Connection,
+    query_words: &[String],
+    word_scores: &mut HashMap<(usize, String), Option<u32>>,
+) -> Option<String> {
+    let mut statement = database.prepare("SELECT word FROM NameWords").unwrap();
+    let name_words = statement
+        .query_map(NO_PARAMS, |row| row.get::<_, String>(0))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let mut word_queries = vec![];
+
+    for (query_word_idx, query_word) in query_words.iter().enumerate() {
+        let mut terms = name_words
+            .iter()
+            .filter(|name_word| !name_word.starts_with(query_word.as_str()))
+            .filter(|name_word| {
+                word_scores
+                    .entry((query_word_idx, name_word.to_string()))
+                    .or_insert_with(|| score_word(query_word, name_word))
+                    .is_some()
+            })
+            .map(|name_word| format!("\"{}\"", name_word))
+            .collect::<Vec<_>>();
+
+        // Name words consist of letters and digits only, so no other query word
+        // can be a prefix of one.
+        if query_word.chars().all(|c| c.is_alphanumeric()) {
+            terms.push(format!("\"{}\" *", query_word));
+        }
+
+        if terms.is_empty() {
+            return None;
+        }
+
+        word_queries.push(format!("({})", terms.join(" OR ")));
+    }
+
+    Some(format!(
+        "{{name aliases}} : ({})",
+        word_queries.join(" AND ")
+    ))
+}
+
+fn score_name(
+    query_words: &[String],
+    name: &str,
+    word_scores: &mut HashMap<(usize, String), Option<u32>>,
+) -> Option<u32> {
+    let name_words = split_into_words(name);
+    let mut total_score = 0;
+
+    for (query_word_idx, query_word) in query_words.iter().enumerate() {
+        let best_score = name_words
+            .iter()
+            .filter_map(|name_word| {
+                *word_scores
+                    .entry((query_word_idx, name_word.clone()))
+                    .or_insert_with(|| score_word(query_word, name_word))
+            })
+            .max()?;
+        total_score += best_score;
+    }
+
+    let extra_word_count = name_words.len().saturating_sub(query_words.len()) as u32;
+    let average_score = total_score / query_words.len() as u32;
+
+    Some(average_score - min(extra_word_count, MAX_EXTRA_WORD_PENALTY))
+}
+
+/// Loads the ranked characters in their order of rank through a temporary table,
+/// as there may be more of them than parameters can be bound to a statement.
+fn retrieve_ranked_characters(
+    database: &Connection,
+    ranked_codepoints: Vec<(u32, u32, usize)>,
+) -> Vec<UnicodeCharacter> {
+    database
+        .execute_batch(
+            "DROP TABLE IF EXISTS temp.FuzzyMatches; \
+             CREATE TEMP TABLE FuzzyMatches (\
+                 position INTEGER PRIMARY KEY, codepoint INTEGER, score INTEGER\
+             );",
+        )
+        .unwrap();
+
+    let mut insert_statement = database
+        .prepare("INSERT INTO temp.FuzzyMatches (codepoint, score) VALUES (?, ?)")
+        .unwrap();
+
+    for (codepoint, score, _) in ranked_codepoints {
+        insert_statement.execute(params![codepoint, score]).unwrap();
+    }
+
+    let sql = format!(
+        "SELECT {}, FuzzyMatches.score AS score \
+         FROM temp.FuzzyMatches JOIN UnicodeData USING (codepoint) \
+         ORDER BY FuzzyMatches.position",
+        CHARACTER_COLUMNS
+    );
+    let mut statement = database.prepare(&sql).unwrap();
+    let mut rows = statement.query(NO_PARAMS).unwrap();
+    let mut characters = vec![];
+
+    while let Some(row) = rows.next().unwrap() {
+        let mut character = UnicodeCharacter::from_row(row);
+        character.score = Some(row.get_unwrap("score"));
+        characters.push(character);
+    }
+
+    characters
+}
+
+fn score_word(query_word: &str, name_word: &str) -> Option<u32> {
+    if query_word == name_word {
+        return Some(EXACT_MATCH_SCORE);
+    }
+
+    if name_word.starts_with(query_word) {
+        return Some(PREFIX_MATCH_SCORE);
+    }
+
+    let max_distance = match query_word.chars().count() {
+        0..=3 => 0,
+        4..=5 => 1,
+        6..=9 => 2,
+        _ => 3,
+    };
+
+    let length_difference =
+        (query_word.chars().count() as isize - name_word.chars().count() as isize).abs();
+
+    if max_distance == 0 || length_difference > max_distance as isize {
+        return None;
+    }
+
+    let distance = edit_distance(query_word, name_word);
+
+    if distance <= max_distance {
+        Some(EDIT_DISTANCE_MATCH_SCORE - EDIT_DISTANCE_PENALTY * (distance as u32 - 1))
+    } else {
+        None
+    }
+}
+
+/// Computes the Damerau-Levenshtein distance in its optimal string alignment variant,
+/// so that swapped adjacent letters count as a single typo.
+fn edit_distance(first: &str, second: &str) -> usize {
+    let first = first.chars().collect::<Vec<_>>();
+    let second = second.chars().collect::<Vec<_>>();
+
+    if (first.len() as isize - second.len() as isize).abs() > 3 {
+        return usize::MAX;
+    }
+
+    let mut distances = vec![vec![0; second.len() + 1]; first.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=first.len() {
+        for j in 1..=second.len() {
+            let substitution_cost = if first[i - 1] == second[j - 1] { 0 } else { 1 };
+
+            distances[i][j] = min(
+                min(distances[i - 1][j] + 1, distances[i][j - 1] + 1),
+                distances[i - 1][j - 1] + substitution_cost,
+            );
+
+            if i > 1 && j > 1 && first[i - 1] == second[j - 2] && first[i - 2] == second[j - 1] {
+                distances[i][j] = min(distances[i][j], distances[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    distances[first.len()][second.len()]
+}
+
+fn split_into_words(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_uppercase())
+        .collect()
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+const MAX_CODEPOINT: u32 = 0x10FFFF;
+
+pub enum CharInput {
+    Single(u32),
+    Range(u32, u32),
+}
+
+/// Parses a literal character, a code point in one of the notations
+/// `U+1F600`, `0x1F600`, `\u{1F600}` or `128512`, or a range of code points
+/// such as `U+0400..U+04FF`.
+///
+/// The bounds of a range are written in the same notations as a single code point,
+/// so unprefixed bounds are decimal numbers as well, such as `128512..128515`.
+pub fn parse_char_input(input: &str) -> Result<CharInput, String> {
+    if input.chars().count() == 1 {
+        return Ok(CharInput::Single(input.chars().next().unwrap() as u32));
+    }
+
+    if let Some(separator_idx) = input.find("..") {
+        let start = parse_range_bound(&input[..separator_idx], input)?;
+        let end = parse_range_bound(&input[separator_idx + 2..], input)?;
+
+        if start > end {
+            return Err(format!("range '{}' must not start after its end", input));
+        }
+
+        return Ok(CharInput::Range(start, end));
+    }
+
+    parse_prefixed_codepoint(input)
+        .or_else(|| parse_number(input, 10))
+        .ok_or_else(|| invalid_input_error(input))
+        .and_then(|codepoint| validate_codepoint(codepoint, input))
+        .map(CharInput::Single)
+}
+
+fn parse_range_bound(bound: &str, input: &str) -> Result<u32, String> {
+    parse_prefixed_codepoint(bound)
+        .or_else(|| parse_number(bound, 10))
+        .ok_or_else(|| {
+            format!(
+                "'{}' is not a valid range of code points \
+                 in the notation U+0400..U+04FF, 0x400..0x4FF or 1024..1279",
+                input
+            )
+        })
+        .and_then(|codepoint| validate_codepoint(codepoint, input))
+}
+
+fn parse_prefixed_codepoint(input: &str) -> Option<u32> {
+    let hex_digits = if let Some(digits) = strip_prefix_ignoring_case(input, "U+") {
+        digits
+    } else if let Some(digits) = strip_prefix_ignoring_case(input, "0x") {
+        digits
+    } else if let Some(digits) = strip_prefix_ignoring_case(input, "\\u{") {
+        digits.strip_suffix('}')?
+    } else {
+        return None;
+    };
+
+    parse_number(hex_digits, 16)
+}
+
+fn strip_prefix_ignoring_case<'a>(input: &'a str, prefix: &str) -> Option<&'a str> {
+    if input.len() >= prefix.len()
+        && input.is_char_boundary(prefix.len())
+        && input[..prefix.len()].eq_ignore_ascii_case(prefix)
+    {
+        Some(&input[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn parse_number(digits: &str, radix: u32) -> Option<u32> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    u32::from_str_radix(digits, radix).ok()
+}
+
+fn validate_codepoint(codepoint: u32, input: &str) -> Result<u32, String> {
+    if codepoint > MAX_CODEPOINT {
+        Err(format!(
+            "'{}' is beyond the last Unicode code point U+10FFFF",
+            input
+        ))
+    } else {
+        Ok(codepoint)
+    }
+}
+
+fn invalid_input_error(input: &str) -> String {
+    format!(
+        "'{}' is neither a single character nor a code point \
+         in the notation U+1F600, 0x1F600, \\u{{1F600}} or 128512",
+        input
+    )
+}
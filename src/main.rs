@@ -14,25 +14,77 @@
  * limitations under the License.
  */
 
+mod bidi;
+mod bidi_class;
+mod case;
 mod category;
+mod character;
+mod decomposition;
+mod filter;
+mod fuzzy;
+mod input;
+mod missing;
+mod normalize;
+mod numeric;
+mod output;
+mod query;
+mod sort;
+mod template;
+mod unicode_set;
 
-use crate::category::Category;
-use colored::Colorize;
+use crate::bidi::{BidiResolver, ParagraphDirection};
+use crate::case::{parse_language, CaseConversion, CaseConverter};
+use crate::character::{
+    Annotation, GraphemeCluster, SearchResults, UnicodeCharacter, CHARACTER_COLUMNS,
+};
+use crate::decomposition::{find_variants, Decomposition};
+use crate::filter::{AgeFilter, BidiClassFilter, BlockFilter, CategoryFilter};
+use crate::fuzzy::rank_by_name;
+use crate::input::{parse_char_input, CharInput};
+use crate::missing::MissingReason;
+use crate::normalize::{NormalizationForm, Normalizer};
+use crate::numeric::DigitParser;
+use crate::output::{
+    convert_results_to_records, format_as_csv, format_as_json, format_as_ndjson, format_as_ranges,
+    format_as_table, format_as_text, format_bidi_paragraphs, format_codepoint_sequence,
+    format_normalization_segments, OutputFormat,
+};
+use crate::query::NameQuery;
+use crate::sort::SortOrder;
+use crate::template::Template;
+use crate::unicode_set::UnicodeSet;
 use dirs::home_dir;
-use rusqlite::{Connection, Row, ToSql, NO_PARAMS};
-use std::char;
-use std::fmt::Write as FmtWrite;
-use std::fs::{create_dir, File};
-use std::io::{Cursor, Read, Write};
+use regex::{Regex, RegexBuilder};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{Connection, ToSql};
+use std::collections::HashMap;
+use std::fmt::{Arguments, Write as FmtWrite};
+use std::fs::{create_dir, read_dir, read_to_string, remove_file, File};
+use std::io::{self, Cursor, Read, Write};
 use std::iter;
-use std::path::Path;
-use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::Arc;
 use structopt::clap::AppSettings::ColoredHelp;
+use structopt::clap::{Error as ClapError, ErrorKind};
 use structopt::StructOpt;
+use strum::VariantNames;
+use unicode_segmentation::UnicodeSegmentation;
 use zip::ZipArchive;
 
 const DATABASE_DIRECTORY_NAME: &str = ".chr";
-const DATABASE_FILE_NAME: &str = "chr_1_0_0.db";
+const EXIT_CODE_SOME_CHARS_MISSING: i32 = 2;
+const EXIT_CODE_ALL_CHARS_MISSING: i32 = 3;
+const EXIT_CODE_NOT_NORMALIZED: i32 = 4;
+const UNICODE_VERSION: &str = env!("CHR_UNICODE_VERSION");
+const VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (Unicode ",
+    env!("CHR_UNICODE_VERSION"),
+    ")"
+);
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 #[derive(StructOpt)]
 #[structopt(
@@ -45,17 +97,20 @@ const DATABASE_FILE_NAME: &str = "chr_1_0_0.db";
     version_short = "v",
     global_settings = &[ColoredHelp]
 )]
-struct CLI {
+struct Cli {
     // --------------------
     // ARGS
     // --------------------
     #[structopt(
         value_name = "CHARS",
-        required_unless = "name",
-        conflicts_with = "name",
-        help = "One or more characters separated by blank space"
+        required_unless_one = &["name", "name-regex", "fuzzy", "set", "variants", "string", "file", "category", "bidi-class", "block", "age"],
+        conflicts_with_all = &["name", "name-regex", "fuzzy", "set", "variants", "string", "file", "normalize", "case", "bidi", "parse-digits", "category", "bidi-class", "block", "age"],
+        parse(try_from_str = parse_char_input),
+        help = "One or more characters or code points separated by blank space,\n\
+                code points may be given as U+1F600, 0x1F600, \\u{1F600}\n\
+                or 128512 and ranges of them as U+0400..U+04FF"
     )]
-    chars: Vec<char>,
+    chars: Vec<CharInput>,
 
     // --------------------
     // FLAGS
@@ -77,6 +132,91 @@ struct CLI {
     )]
     is_output_colorized: bool,
 
+    #[structopt(
+        name = "whole-words",
+        short,
+        long,
+        requires = "name",
+        help = "Matches all terms of the name search as whole words",
+        display_order = 3
+    )]
+    is_whole_word_match: bool,
+
+    #[structopt(
+        name = "ranges",
+        long,
+        requires = "set",
+        conflicts_with_all = &["format", "template"],
+        help = "Prints the code points of the set as compact ranges",
+        display_order = 4
+    )]
+    is_range_output: bool,
+
+    #[structopt(
+        name = "input-order",
+        long,
+        requires = "chars",
+        conflicts_with = "sort",
+        help = "Lists the characters in the order they are given,\n\
+                including duplicates",
+        display_order = 5
+    )]
+    is_input_order_kept: bool,
+
+    #[structopt(
+        name = "count",
+        long,
+        conflicts_with_all = &["format", "template", "limit", "offset"],
+        help = "Prints only the number of results",
+        display_order = 6
+    )]
+    is_count_only: bool,
+
+    #[structopt(
+        name = "details",
+        short,
+        long,
+        conflicts_with_all = &["count", "ranges"],
+        help = "Adds the annotations of the Unicode code charts\n\
+                such as informal aliases, notes and cross references\n\
+                as well as the combining class, decomposition,\n\
+                case mappings, bidi properties and numeric value",
+        display_order = 7
+    )]
+    is_detailed: bool,
+
+    #[structopt(
+        name = "check",
+        long,
+        requires = "normalize",
+        help = "Only checks whether the text is normalized already,\n\
+                exits with code 4 if it is not",
+        display_order = 8
+    )]
+    is_normalization_checked: bool,
+
+    #[structopt(
+        name = "normalized-only",
+        long,
+        requires = "normalize",
+        conflicts_with = "check",
+        help = "Prints only the normalized text",
+        display_order = 9
+    )]
+    is_normalized_text_only: bool,
+
+    #[structopt(
+        name = "parse-digits",
+        long,
+        requires = "string",
+        conflicts_with_all = &["name", "name-regex", "fuzzy", "set", "variants", "normalize", "case", "bidi", "category", "bidi-class", "block", "age"],
+        help = "Parses a string of decimal digits of any script\n\
+                such as ١٢٣ or ४२ into an ASCII integer,\n\
+                rejecting digits of different numbering systems",
+        display_order = 10
+    )]
+    is_digit_parsing: bool,
+
     // --------------------
     // OPTIONS
     // --------------------
@@ -85,34 +225,377 @@ struct CLI {
         value_name = "NAME",
         short,
         long,
-        required_unless = "chars",
-        help = "Searches for characters by their name as\n\
-                stated in the Unicode Character Database"
+        conflicts_with = "string",
+        help = "Searches for characters by their name or alias\n\
+                as stated in the Unicode Character Database,\n\
+                also within words unless matched as whole words,\n\
+                terms can be combined with AND, OR and NOT\n\
+                and phrases of whole words written in quotes"
+    )]
+    name: Option<NameQuery>,
+
+    #[structopt(
+        name = "name-regex",
+        value_name = "REGEX",
+        short = "r",
+        long,
+        conflicts_with = "string",
+        parse(try_from_str = parse_name_regex),
+        help = "Searches for characters whose name matches\n\
+                a regular expression, ignoring case"
+    )]
+    name_regex: Option<String>,
+
+    #[structopt(
+        name = "fuzzy",
+        value_name = "NAME",
+        short = "z",
+        long,
+        conflicts_with_all = &["name", "name-regex", "string"],
+        help = "Searches for characters by their name or alias\n\
+                while tolerating misspellings, ranked by relevance"
+    )]
+    fuzzy: Option<String>,
+
+    #[structopt(
+        name = "set",
+        value_name = "SET",
+        short = "u",
+        long,
+        conflicts_with_all = &["name", "name-regex", "fuzzy", "variants", "string", "category", "bidi-class", "block", "age"],
+        help = "Searches for characters in a UnicodeSet expression\n\
+                such as '[\\p{Lu}&&\\p{Block=Greek_and_Coptic}]',\n\
+                '[\\p{L}-\\p{Latin}]' or '[a-zà-ÿ]'"
+    )]
+    set: Option<UnicodeSet>,
+
+    #[structopt(
+        name = "variants",
+        value_name = "BASE",
+        long,
+        conflicts_with_all = &["name", "name-regex", "fuzzy", "string"],
+        help = "Searches for characters whose canonical or compatibility\n\
+                decomposition contains the given character or string,\n\
+                grouped by the tag of the decomposition"
+    )]
+    variants: Option<String>,
+
+    #[structopt(
+        name = "string",
+        value_name = "STRING",
+        short,
+        long,
+        help = "Splits a string into its grapheme clusters\n\
+                and gives information about their characters"
+    )]
+    string: Option<String>,
+
+    #[structopt(
+        name = "normalize",
+        value_name = "FORM",
+        long,
+        conflicts_with_all = &["name", "name-regex", "fuzzy", "set", "variants", "category", "bidi-class", "block", "age"],
+        help = "Converts a string or file to the normalization form\n\
+                NFC, NFD, NFKC or NFKD and shows the code points\n\
+                before and after wherever they change"
+    )]
+    normalization_form: Option<NormalizationForm>,
+
+    #[structopt(
+        name = "case",
+        value_name = "CASE",
+        long,
+        requires = "string",
+        conflicts_with_all = &["name", "name-regex", "fuzzy", "set", "variants", "normalize", "category", "bidi-class", "block", "age"],
+        help = "Converts a string to upper, lower, title or fold case\n\
+                with the full case mappings, which may change\n\
+                the length of the string such as ß to SS"
+    )]
+    case_conversion: Option<CaseConversion>,
+
+    #[structopt(
+        name = "language",
+        value_name = "LANGUAGE",
+        long,
+        requires = "case",
+        parse(try_from_str = parse_language),
+        help = "Applies the case mappings tailored to a language\n\
+                such as tr and az for the dotless i of Turkish\n\
+                and Azerbaijani or lt for the dotted i of Lithuanian"
     )]
-    name: Option<String>,
+    language: Option<String>,
+
+    #[structopt(
+        name = "bidi",
+        value_name = "DIRECTION",
+        long,
+        requires = "string",
+        conflicts_with_all = &["name", "name-regex", "fuzzy", "set", "variants", "normalize", "case", "category", "bidi-class", "block", "age"],
+        help = "Runs the bidirectional algorithm on a string\n\
+                for the paragraph direction ltr, rtl or auto and\n\
+                shows the resolved levels, the runs and the visual order"
+    )]
+    bidi_direction: Option<ParagraphDirection>,
+
+    #[structopt(
+        name = "file",
+        value_name = "FILE",
+        long,
+        requires = "normalize",
+        conflicts_with = "string",
+        parse(from_os_str),
+        help = "Reads the text to normalize from a file"
+    )]
+    file: Option<PathBuf>,
+
+    #[structopt(
+        name = "category",
+        value_name = "CATEGORY",
+        long,
+        conflicts_with = "string",
+        help = "Only includes characters of the given general categories,\n\
+                separated by commas, such as Lu, L or 'Uppercase Letter'"
+    )]
+    category: Option<CategoryFilter>,
+
+    #[structopt(
+        name = "bidi-class",
+        value_name = "BIDI_CLASS",
+        long,
+        conflicts_with = "string",
+        help = "Only includes characters of the given bidi classes,\n\
+                separated by commas, such as AL, 'Arabic Letter'\n\
+                or one of strong, weak, neutral or explicit formatting"
+    )]
+    bidi_class: Option<BidiClassFilter>,
+
+    #[structopt(
+        name = "block",
+        value_name = "BLOCK",
+        long,
+        conflicts_with = "string",
+        help = "Only includes characters of the given blocks,\n\
+                separated by commas, such as 'Latin Extended-A'"
+    )]
+    block: Option<BlockFilter>,
+
+    #[structopt(
+        name = "age",
+        value_name = "AGE",
+        long,
+        conflicts_with = "string",
+        help = "Only includes characters added in the given Unicode\n\
+                version, such as 13.0, >=6.0, <3.0 or 6.0..8.0"
+    )]
+    age: Option<AgeFilter>,
+
+    #[structopt(
+        name = "sort",
+        value_name = "SORT",
+        long,
+        conflicts_with = "string",
+        help = "Sorts the results by a field, optionally followed\n\
+                by :asc or :desc, such as name or age:desc"
+    )]
+    sort: Option<SortOrder>,
+
+    #[structopt(
+        name = "limit",
+        value_name = "LIMIT",
+        long,
+        help = "Lists at most the given number of results"
+    )]
+    limit: Option<usize>,
+
+    #[structopt(
+        name = "offset",
+        value_name = "OFFSET",
+        long,
+        default_value = "0",
+        help = "Skips the given number of results"
+    )]
+    offset: usize,
+
+    #[structopt(
+        name = "format",
+        value_name = "FORMAT",
+        short,
+        long,
+        default_value = "text",
+        possible_values = OutputFormat::VARIANTS,
+        help = "Sets the output format, only text is colorized\n\
+                and only text and table are paged"
+    )]
+    output_format: OutputFormat,
+
+    #[structopt(
+        name = "template",
+        value_name = "TEMPLATE",
+        short,
+        long,
+        conflicts_with = "format",
+        help = "Prints each character with a template such as\n\
+                '{char}\\t{codepoint:04x}\\t{name:lower}', available\n\
+                fields are char, codepoint, hex, name, category,\n\
+                category_description, block, age, aliases,\n\
+                unicode_1_name, iso_comment, combining_class,\n\
+                decomposition, uppercase, lowercase, titlecase,\n\
+                case_folding, bidi_class, bidi_mirrored,\n\
+                bidi_mirroring_glyph, bidi_paired_bracket,\n\
+                bidi_paired_bracket_type, numeric_type,\n\
+                numeric_value, decomposition_group,\n\
+                grapheme_cluster, score"
+    )]
+    template: Option<Template>,
 }
 
 fn main() {
-    let cli: CLI = CLI::from_args();
+    let cli = Cli::from_clap(&Cli::clap().version(VERSION).get_matches());
     let database = connect_to_database();
-    let results = search_database(database, &cli);
 
-    render(results, &cli);
+    if let (Some(unicode_set), true) = (&cli.set, cli.is_range_output) {
+        for range in format_as_ranges(&evaluate_unicode_set(&database, unicode_set)) {
+            write_to_stdout(format_args!("{}\n", range));
+        }
+        return;
+    }
+
+    if let Some(normalization_form) = cli.normalization_form {
+        normalize_text(&database, normalization_form, &cli);
+        return;
+    }
+
+    if let (Some(case_conversion), Some(string)) = (cli.case_conversion, &cli.string) {
+        let case_converter = CaseConverter::load(&database);
+        println!(
+            "{}",
+            case_converter.convert(string, case_conversion, cli.language.as_deref())
+        );
+        return;
+    }
+
+    if let (true, Some(string)) = (cli.is_digit_parsing, &cli.string) {
+        let digit_parser = DigitParser::load(&database);
+        match digit_parser.parse(string) {
+            Ok(integer) => println!("{}", integer),
+            Err(message) => exit_with_invalid_value("--string <STRING>", &message),
+        }
+        return;
+    }
+
+    if let (Some(direction), Some(string)) = (cli.bidi_direction, &cli.string) {
+        resolve_bidi_text(&database, direction, string, &cli);
+        return;
+    }
+
+    // Set, name and filter searches are counted, sorted and paginated by the database,
+    // all other searches only after their results have been processed.
+    let database_result_count = count_database(&database, &cli);
+
+    if let (true, Some(result_count)) = (cli.is_count_only, database_result_count) {
+        println!("{}", result_count);
+        return;
+    }
+
+    let database_version = read_database_version(&database);
+    let mut results = search_database(&database, &cli);
+
+    if database_result_count.is_none() {
+        if let (Some(sort_order), SearchResults::Characters(characters)) = (&cli.sort, &mut results)
+        {
+            sort_order.sort(characters);
+        }
+    }
+
+    let missing_codepoints = find_missing_codepoints(&results, &cli);
+    let result_count = database_result_count.unwrap_or_else(|| results.len());
+
+    if cli.is_count_only {
+        println!("{}", result_count);
+    } else if result_count > 0 || missing_codepoints.is_empty() {
+        let mut results = if database_result_count.is_some() {
+            results
+        } else {
+            results.paginate(cli.offset, cli.limit)
+        };
+
+        expand_decompositions(&database, &mut results);
+
+        if cli.is_detailed {
+            retrieve_annotations(&database, &mut results);
+        }
+
+        render(results, result_count, &cli);
+    }
+
+    if !missing_codepoints.is_empty() {
+        for codepoint in missing_codepoints {
+            let reason = MissingReason::of(&database, codepoint, &database_version);
+            eprintln!(
+                "U+{:04X} {} is not in the database: {}",
+                codepoint,
+                reason.label(codepoint),
+                reason
+            );
+        }
+
+        exit(if result_count > 0 {
+            EXIT_CODE_SOME_CHARS_MISSING
+        } else {
+            EXIT_CODE_ALL_CHARS_MISSING
+        });
+    }
 }
 
 fn connect_to_database() -> Connection {
     let home_directory = home_dir().expect("Home directory could not be found");
     let database_file_path = home_directory
         .join(DATABASE_DIRECTORY_NAME)
-        .join(DATABASE_FILE_NAME);
+        .join(database_file_name());
 
     if !database_file_path.is_file() {
-        println!("Preparing Unicode character database, please wait a moment...");
+        eprintln!("Preparing Unicode character database, please wait a moment...");
         unzip_database(&home_directory);
-        println!("Database is ready\n");
+        eprintln!("Database is ready\n");
     }
 
-    Connection::open(database_file_path).expect("Database connection could not be established")
+    let database =
+        Connection::open(database_file_path).expect("Database connection could not be established");
+
+    add_regexp_function(&database);
+
+    database
+}
+
+fn add_regexp_function(database: &Connection) {
+    database
+        .create_scalar_function(
+            "regexp",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let regex: Arc<Regex> = ctx.get_or_create_aux(0, |pattern| {
+                    build_name_regex(pattern.as_str()?).map_err(BoxError::from)
+                })?;
+                let text = ctx
+                    .get_raw(1)
+                    .as_str()
+                    .map_err(|err| rusqlite::Error::UserFunctionError(err.into()))?;
+
+                Ok(regex.is_match(text))
+            },
+        )
+        .expect("Regular expression function could not be added to database");
+}
+
+fn build_name_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+fn parse_name_regex(pattern: &str) -> Result<String, String> {
+    build_name_regex(pattern)
+        .map(|_| pattern.to_string())
+        .map_err(|err| err.to_string())
 }
 
 fn unzip_database(home_directory: &Path) {
@@ -135,121 +618,641 @@ fn unzip_database(home_directory: &Path) {
         create_dir(&database_directory).expect("Database directory could not be created");
     }
 
-    let database_file_path = database_directory.join(DATABASE_FILE_NAME);
+    let database_file_path = database_directory.join(database_file_name());
     let mut database_file =
         File::create(database_file_path).expect("Database file could not be created");
 
     database_file
         .write_all(&database_file_bytes)
         .expect("Database content could not be written to file");
+
+    remove_outdated_databases(&database_directory);
+}
+
+/// Removes the databases unzipped by other builds, whose file names differ
+/// in the Unicode version or the checksum. Databases that are still in use
+/// and cannot be removed are left behind for the next time.
+fn remove_outdated_databases(database_directory: &Path) {
+    let current_file_name = database_file_name();
+    let entries = match read_dir(database_directory) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let is_outdated_database = file_name.starts_with("chr_")
+            && file_name.contains("_unicode_")
+            && file_name.ends_with(".db")
+            && file_name != current_file_name;
+
+        if is_outdated_database {
+            let _ = remove_file(entry.path());
+        }
+    }
+}
+
+fn read_database_version(database: &Connection) -> String {
+    database
+        .query_row(
+            "SELECT value FROM Metadata WHERE key = 'unicode_version'",
+            rusqlite::NO_PARAMS,
+            |row| row.get(0),
+        )
+        .expect("Unicode version could not be read from database")
+}
+
+/// Returns the code points that were looked up directly, either as single
+/// characters or within a string, but could not be found in the database.
+fn find_missing_codepoints(results: &SearchResults, cli: &Cli) -> Vec<u32> {
+    let found_codepoints = match results {
+        SearchResults::Characters(characters) => characters
+            .iter()
+            .map(|character| character.codepoint)
+            .collect::<Vec<_>>(),
+        SearchResults::GraphemeClusters(grapheme_clusters) => grapheme_clusters
+            .iter()
+            .flat_map(|grapheme_cluster| &grapheme_cluster.characters)
+            .map(|character| character.codepoint)
+            .collect::<Vec<_>>(),
+    };
+
+    let requested_codepoints = match &cli.string {
+        Some(string) => string.chars().map(to_decimal_number).collect::<Vec<_>>(),
+        None => cli
+            .chars
+            .iter()
+            .filter_map(|char_input| match char_input {
+                CharInput::Single(codepoint) => Some(*codepoint),
+                CharInput::Range(_, _) => None,
+            })
+            .collect::<Vec<_>>(),
+    };
+
+    let mut missing_codepoints = vec![];
+
+    for codepoint in requested_codepoints {
+        if !found_codepoints.contains(&codepoint) && !missing_codepoints.contains(&codepoint) {
+            missing_codepoints.push(codepoint);
+        }
+    }
+
+    missing_codepoints
+}
+
+fn database_file_name() -> String {
+    format!(
+        "chr_1_0_0_unicode_{}_{}.db",
+        UNICODE_VERSION.replace('.', "_"),
+        env!("CHR_DATABASE_CHECKSUM")
+    )
+}
+
+fn search_database(database: &Connection, cli: &Cli) -> SearchResults {
+    if let Some(string) = &cli.string {
+        SearchResults::GraphemeClusters(search_grapheme_clusters(database, string))
+    } else if !cli.chars.is_empty() {
+        let (sql, params) = create_codepoint_query(&cli.chars);
+        let characters = retrieve_results(database, sql, params);
+
+        if cli.is_input_order_kept {
+            SearchResults::Characters(arrange_in_input_order(characters, &cli.chars))
+        } else {
+            SearchResults::Characters(characters)
+        }
+    } else if let Some(unicode_set) = &cli.set {
+        let char_inputs = evaluate_unicode_set(database, unicode_set)
+            .into_iter()
+            .map(|(start, end)| CharInput::Range(start, end))
+            .collect::<Vec<_>>();
+
+        if char_inputs.is_empty() {
+            return SearchResults::Characters(vec![]);
+        }
+
+        let (condition, params) = create_codepoint_condition(&char_inputs);
+        let sql = format!(
+            "SELECT {} FROM UnicodeData WHERE {} {}",
+            CHARACTER_COLUMNS,
+            condition,
+            create_page_clause(cli, "codepoint")
+        );
+        SearchResults::Characters(retrieve_results(database, sql, params))
+    } else if let Some(base) = &cli.variants {
+        let mut params = vec![];
+        let mut conditions = create_filter_conditions(database, cli, &mut params);
+        conditions.insert(0, String::from("decomposition IS NOT NULL"));
+        let sql = format!(
+            "SELECT {} FROM UnicodeData WHERE {}",
+            CHARACTER_COLUMNS,
+            conditions.join(" AND ")
+        );
+        let characters = retrieve_results(database, sql, params);
+        let mappings = retrieve_decomposition_mappings(database);
+        SearchResults::Characters(find_variants(base, characters, &mappings))
+    } else if let Some(fuzzy_query) = &cli.fuzzy {
+        let mut params = vec![];
+        let conditions = create_filter_conditions(database, cli, &mut params);
+        SearchResults::Characters(rank_by_name(database, fuzzy_query, &conditions, &params))
+    } else {
+        let mut params = vec![];
+        let mut ranking = String::new();
+        let mut exact_names = vec![];
+
+        if let Some(name_query) = &cli.name {
+            if let Some(ranking_query) = name_query.to_ranking_query(cli.is_whole_word_match) {
+                ranking = String::from(
+                    "LEFT JOIN (SELECT rowid, rank FROM NameIndex WHERE NameIndex MATCH ?) \
+                     AS NameRanking ON NameRanking.rowid = codepoint",
+                );
+                params.push(ranking_query);
+                exact_names = name_query.to_exact_names();
+            }
+        }
+
+        let conditions = create_search_conditions(database, cli, &mut params);
+
+        // Characters whose name or alias equals a term of the query rank
+        // above all others, which are ranked by their relevance.
+        let order = if ranking.is_empty() {
+            String::from("codepoint")
+        } else {
+            let placeholders = vec!["?"; exact_names.len()].join(", ");
+            params.extend(exact_names.iter().cloned());
+            params.extend(exact_names);
+            format!(
+                "CASE WHEN name IN ({0}) OR codepoint IN \
+                 (SELECT codepoint FROM NameAliases WHERE alias IN ({0})) THEN 0 ELSE 1 END, \
+                 NameRanking.rank IS NULL, NameRanking.rank, codepoint",
+                placeholders
+            )
+        };
+
+        let sql = format!(
+            "SELECT {} FROM UnicodeData {} WHERE {} {}",
+            CHARACTER_COLUMNS,
+            ranking,
+            conditions.join(" AND "),
+            create_page_clause(cli, &order)
+        );
+        SearchResults::Characters(retrieve_results(database, sql, params))
+    }
+}
+
+/// Creates the `ORDER BY`, `LIMIT` and `OFFSET` clauses of set, name and filter searches,
+/// so that only the requested page of results is loaded. The sort order given by `--sort`
+/// takes precedence over `default_order`, which only decides between equal sort keys.
+fn create_page_clause(cli: &Cli, default_order: &str) -> String {
+    let order = match &cli.sort {
+        Some(sort_order) => format!("{}, {}", sort_order.to_sql(), default_order),
+        None => default_order.to_string(),
+    };
+    let limit = cli.limit.map_or(-1, |limit| limit as i64);
+
+    format!("ORDER BY {} LIMIT {} OFFSET {}", order, limit, cli.offset)
+}
+
+/// Counts the results of set, name and filter searches without loading them. Returns `None`
+/// for the other searches, whose results are only known after processing the characters.
+fn count_database(database: &Connection, cli: &Cli) -> Option<usize> {
+    let (condition, params) = if let Some(unicode_set) = &cli.set {
+        let char_inputs = evaluate_unicode_set(database, unicode_set)
+            .into_iter()
+            .map(|(start, end)| CharInput::Range(start, end))
+            .collect::<Vec<_>>();
+
+        if char_inputs.is_empty() {
+            return Some(0);
+        }
+
+        let (condition, params) = create_codepoint_condition(&char_inputs);
+        (
+            condition,
+            params.iter().map(|param| param.to_string()).collect(),
+        )
+    } else if cli.string.is_none()
+        && cli.chars.is_empty()
+        && cli.variants.is_none()
+        && cli.fuzzy.is_none()
+    {
+        let mut params = vec![];
+        let conditions = create_search_conditions(database, cli, &mut params);
+        (conditions.join(" AND "), params)
+    } else {
+        return None;
+    };
+
+    let sql = format!("SELECT COUNT(*) FROM UnicodeData WHERE {}", condition);
+    let count: u32 = database.query_row(&sql, params, |row| row.get(0)).unwrap();
+
+    Some(count as usize)
+}
+
+fn create_search_conditions(
+    database: &Connection,
+    cli: &Cli,
+    params: &mut Vec<String>,
+) -> Vec<String> {
+    let mut conditions = vec![];
+
+    if let Some(name_query) = &cli.name {
+        conditions.push(name_query.to_sql(cli.is_whole_word_match, params));
+    }
+
+    if let Some(name_regex) = &cli.name_regex {
+        conditions.push(String::from("name REGEXP ?"));
+        params.push(name_regex.clone());
+    }
+
+    conditions.extend(create_filter_conditions(database, cli, params));
+    conditions
+}
+
+fn create_filter_conditions(
+    database: &Connection,
+    cli: &Cli,
+    params: &mut Vec<String>,
+) -> Vec<String> {
+    let mut conditions = vec![];
+
+    if let Some(category_filter) = &cli.category {
+        if let Err(message) = category_filter.validate() {
+            exit_with_invalid_value("--category <CATEGORY>", &message);
+        }
+        conditions.push(category_filter.to_sql(params));
+    }
+
+    if let Some(bidi_class_filter) = &cli.bidi_class {
+        conditions.push(bidi_class_filter.to_sql(params));
+    }
+
+    if let Some(block_filter) = &cli.block {
+        if let Err(message) = block_filter.validate(database) {
+            exit_with_invalid_value("--block <BLOCK>", &message);
+        }
+        conditions.push(block_filter.to_sql(params));
+    }
+
+    if let Some(age_filter) = &cli.age {
+        conditions.push(age_filter.to_sql());
+    }
+
+    conditions
+}
+
+fn evaluate_unicode_set(database: &Connection, unicode_set: &UnicodeSet) -> Vec<(u32, u32)> {
+    unicode_set
+        .evaluate(database)
+        .unwrap_or_else(|message| exit_with_invalid_value("--set <SET>", &message))
+}
+
+fn normalize_text(database: &Connection, normalization_form: NormalizationForm, cli: &Cli) {
+    let text = match (&cli.string, &cli.file) {
+        (Some(string), _) => string.clone(),
+        (None, Some(file_path)) => read_to_string(file_path).unwrap_or_else(|err| {
+            exit_with_invalid_value(
+                "--file <FILE>",
+                &format!("{} could not be read: {}", file_path.display(), err),
+            )
+        }),
+        (None, None) => ClapError::with_description(
+            "The argument '--normalize <FORM>' requires either '--string <STRING>' \
+             or '--file <FILE>'",
+            ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+    };
+    let normalizer = Normalizer::load(database);
+
+    if cli.is_normalized_text_only {
+        print!("{}", normalizer.normalize(&text, normalization_form));
+        return;
+    }
+
+    let changed_segments = normalizer
+        .split_into_segments(&text, normalization_form)
+        .into_iter()
+        .filter(|segment| segment.is_changed())
+        .collect::<Vec<_>>();
+    let location_prefix = cli
+        .file
+        .as_ref()
+        .map(|file_path| format!("{}:", file_path.display()))
+        .unwrap_or_default();
+
+    if changed_segments.is_empty() {
+        println!("Text is already in {}", normalization_form);
+    } else if cli.is_normalization_checked {
+        for segment in changed_segments {
+            println!(
+                "{}{}:{}: {} is not in {}, expected {}",
+                location_prefix,
+                segment.line,
+                segment.column,
+                format_codepoint_sequence(&segment.original),
+                normalization_form,
+                format_codepoint_sequence(&segment.normalized)
+            );
+        }
+
+        exit(EXIT_CODE_NOT_NORMALIZED);
+    } else {
+        let codepoints = changed_segments
+            .iter()
+            .flat_map(|segment| segment.original.iter().chain(&segment.normalized))
+            .copied()
+            .collect::<Vec<_>>();
+        let names = retrieve_names(database, &codepoints);
+
+        page_or_print(
+            &format_normalization_segments(&changed_segments, &names, &location_prefix),
+            cli,
+        );
+    }
+}
+
+fn resolve_bidi_text(database: &Connection, direction: ParagraphDirection, text: &str, cli: &Cli) {
+    let resolver = BidiResolver::load(database, text);
+    let paragraphs = resolver.resolve(text, direction);
+    let codepoints = text.chars().map(to_decimal_number).collect::<Vec<_>>();
+    let names = retrieve_names(database, &codepoints);
+
+    page_or_print(
+        &format_bidi_paragraphs(&paragraphs, &names, cli.is_output_colorized),
+        cli,
+    );
+}
+
+/// Returns the names of the given code points, or a label such as
+/// `<unassigned>` for those that are not in the database.
+fn retrieve_names(database: &Connection, codepoints: &[u32]) -> HashMap<u32, String> {
+    let mut codepoints = codepoints.to_vec();
+
+    codepoints.sort_unstable();
+    codepoints.dedup();
+
+    let char_inputs = codepoints
+        .iter()
+        .map(|codepoint| CharInput::Single(*codepoint))
+        .collect::<Vec<_>>();
+    let (sql, params) = create_codepoint_query(&char_inputs);
+    let database_version = read_database_version(database);
+    let mut names = retrieve_results(database, sql, params)
+        .into_iter()
+        .map(|character| (character.codepoint, character.name))
+        .collect::<HashMap<_, _>>();
+
+    for codepoint in codepoints {
+        names.entry(codepoint).or_insert_with(|| {
+            MissingReason::of(database, codepoint, &database_version).label(codepoint)
+        });
+    }
+
+    names
+}
+
+fn exit_with_invalid_value(argument: &str, message: &str) -> ! {
+    ClapError::with_description(
+        &format!("Invalid value for '{}': {}", argument, message),
+        ErrorKind::InvalidValue,
+    )
+    .exit()
+}
+
+fn search_grapheme_clusters(database: &Connection, string: &str) -> Vec<GraphemeCluster> {
+    let mut codepoints = string.chars().map(to_decimal_number).collect::<Vec<_>>();
+
+    codepoints.sort_unstable();
+    codepoints.dedup();
+
+    let char_inputs = codepoints
+        .into_iter()
+        .map(CharInput::Single)
+        .collect::<Vec<_>>();
+    let (sql, params) = create_codepoint_query(&char_inputs);
+    let characters = retrieve_results(database, sql, params)
+        .into_iter()
+        .map(|character| (character.codepoint, character))
+        .collect::<HashMap<_, _>>();
+
+    string
+        .graphemes(true)
+        .map(|grapheme_cluster| GraphemeCluster {
+            text: grapheme_cluster.to_string(),
+            characters: grapheme_cluster
+                .chars()
+                .filter_map(|c| characters.get(&to_decimal_number(c)).cloned())
+                .collect(),
+        })
+        .collect()
+}
+
+fn arrange_in_input_order(
+    characters: Vec<UnicodeCharacter>,
+    char_inputs: &[CharInput],
+) -> Vec<UnicodeCharacter> {
+    let mut arranged_characters = vec![];
+
+    for char_input in char_inputs {
+        let (start, end) = match char_input {
+            CharInput::Single(codepoint) => (*codepoint, *codepoint),
+            CharInput::Range(start, end) => (*start, *end),
+        };
+        let first_idx = characters.partition_point(|character| character.codepoint < start);
+
+        arranged_characters.extend(
+            characters[first_idx..]
+                .iter()
+                .take_while(|character| character.codepoint <= end)
+                .cloned(),
+        );
+    }
+
+    arranged_characters
 }
 
-fn search_database(database: Connection, cli: &CLI) -> Vec<String> {
-    let mut sql =
-        String::from("SELECT codepoint, name, category, block, age FROM UnicodeData WHERE ");
+fn create_codepoint_query(char_inputs: &[CharInput]) -> (String, Vec<u32>) {
+    let (condition, params) = create_codepoint_condition(char_inputs);
+    let sql = format!(
+        "SELECT {} FROM UnicodeData WHERE {} ORDER BY codepoint",
+        CHARACTER_COLUMNS, condition
+    );
+
+    (sql, params)
+}
+
+fn create_codepoint_condition(char_inputs: &[CharInput]) -> (String, Vec<u32>) {
+    let mut codepoints = vec![];
+    let mut ranges = vec![];
+
+    for char_input in char_inputs {
+        match char_input {
+            CharInput::Single(codepoint) => codepoints.push(*codepoint),
+            CharInput::Range(start, end) => ranges.push((*start, *end)),
+        }
+    }
 
-    if !cli.chars.is_empty() {
-        let chars_as_decimals = convert_chars_to_decimals(&cli.chars);
-        let params = iter::repeat("?")
-            .take(chars_as_decimals.len())
+    let mut conditions = vec![];
+    let mut params = vec![];
+
+    if !codepoints.is_empty() {
+        let placeholders = iter::repeat("?")
+            .take(codepoints.len())
             .collect::<Vec<_>>()
             .join(",");
+        conditions.push(format!("codepoint IN ({})", placeholders));
+        params.extend(codepoints);
+    }
 
-        sql.push_str(&format!("codepoint IN ({})", params));
-        retrieve_results(database, sql, chars_as_decimals, cli)
-    } else {
-        sql.push_str(&format!("name LIKE '%{}%'", cli.name.as_ref().unwrap()));
-        retrieve_results(database, sql, NO_PARAMS, cli)
+    for (start, end) in ranges {
+        conditions.push(String::from("codepoint BETWEEN ? AND ?"));
+        params.push(start);
+        params.push(end);
     }
+
+    (conditions.join(" OR "), params)
 }
 
-fn retrieve_results<P>(database: Connection, sql: String, params: P, cli: &CLI) -> Vec<String>
+fn retrieve_results<P>(database: &Connection, sql: String, params: P) -> Vec<UnicodeCharacter>
 where
     P: IntoIterator,
     P::Item: ToSql,
 {
     let mut statement = database.prepare(&sql).unwrap();
     let mut rows = statement.query(params).unwrap();
-    let mut results = vec![];
-    let mut idx = 1;
+    let mut characters = vec![];
 
     while let Some(row) = rows.next().unwrap() {
-        let result = convert_database_row_to_result(row, idx, cli);
-        results.push(result);
-        idx += 1;
-    }
-
-    results
-}
-
-fn convert_database_row_to_result(row: &Row, idx: u32, cli: &CLI) -> String {
-    let codepoint_column_index = row.column_index("codepoint").unwrap();
-    let name_column_index = row.column_index("name").unwrap();
-    let category_column_index = row.column_index("category").unwrap();
-    let block_column_index = row.column_index("block").unwrap();
-    let age_column_index = row.column_index("age").unwrap();
-
-    let c = char::from_u32(row.get_unwrap(codepoint_column_index)).unwrap();
-    let hex_code = format!("U+{:04x}", to_decimal_number(c)).to_uppercase();
-    let name: String = row.get_unwrap(name_column_index);
-    let category: String = row.get_unwrap(category_column_index);
-    let category_description: &str = Category::from_str(&category).unwrap().description();
-    let block: String = row.get_unwrap(block_column_index);
-    let age: String = row.get_unwrap(age_column_index);
-    let formatted_age = format!("since {}", age);
-
-    if cli.is_output_colorized {
-        let idx_str = format!("{}.", idx);
-        let colored_idx = idx_str.as_str().bright_white().on_bright_blue();
-        let colored_hex_code = hex_code.as_str().green();
-        let colored_name = name.as_str().cyan();
-        let colored_category = category_description.red();
-        let colored_block = block.as_str().purple();
-        let colored_age = formatted_age.as_str().yellow();
-
-        format!(
-            "{}\t{}\t{}\n{}\n{}\t{}\n{}",
-            colored_idx,
-            c,
-            colored_hex_code,
-            colored_name,
-            colored_block,
-            colored_category,
-            colored_age
-        )
-    } else {
-        format!(
-            "{}.\t{}\t{}\n{}\n{}\t{}\n{}",
-            idx, c, hex_code, name, block, category_description, formatted_age
-        )
+        characters.push(UnicodeCharacter::from_row(row));
+    }
+
+    characters
+}
+
+fn retrieve_decomposition_mappings(database: &Connection) -> HashMap<u32, String> {
+    let mut statement = database
+        .prepare("SELECT codepoint, decomposition FROM UnicodeData WHERE decomposition IS NOT NULL")
+        .unwrap();
+
+    statement
+        .query_map(rusqlite::NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<HashMap<_, _>, _>>()
+        .unwrap()
+}
+
+fn expand_decompositions(database: &Connection, results: &mut SearchResults) {
+    for character in results.characters_mut() {
+        character.decomposition = character
+            .decomposition_mapping
+            .as_ref()
+            .map(|mapping| Decomposition::expand(mapping, database));
     }
 }
 
-fn convert_chars_to_decimals(chars: &[char]) -> Vec<u32> {
-    chars.iter().map(|&c| to_decimal_number(c)).collect()
+fn retrieve_annotations(database: &Connection, results: &mut SearchResults) {
+    // Cross references are given the current name of the referenced character
+    // instead of the lowercase one in NamesList.txt.
+    let mut statement = database
+        .prepare(
+            "SELECT type, COALESCE(UnicodeData.name, text) AS text, reference \
+             FROM Annotations LEFT JOIN UnicodeData ON UnicodeData.codepoint = reference \
+             WHERE Annotations.codepoint = ? ORDER BY position",
+        )
+        .unwrap();
+
+    for character in results.characters_mut() {
+        let annotations = statement
+            .query_map([character.codepoint], |row| {
+                Ok(Annotation {
+                    annotation_type: row.get(0)?,
+                    text: row.get(1)?,
+                    reference: row.get(2)?,
+                })
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        character.annotations = Some(annotations);
+    }
 }
 
 fn to_decimal_number(c: char) -> u32 {
-    u32::from_str_radix(&to_hex_code(c), 16).expect("Could not convert hex to decimal number")
+    c as u32
 }
 
-fn to_hex_code(c: char) -> String {
-    let escaped_char = c.escape_unicode().to_string();
-    escaped_char[3..escaped_char.len() - 1].to_string()
-}
+fn render(results: SearchResults, result_count: usize, cli: &Cli) {
+    if let Some(template) = &cli.template {
+        for record in convert_results_to_records(&results) {
+            write_to_stdout(format_args!("{}\n", template.render(&record)));
+        }
+        return;
+    }
+
+    match cli.output_format {
+        OutputFormat::Json => write_to_stdout(format_args!(
+            "{}\n",
+            format_as_json(&convert_results_to_records(&results))
+        )),
+        OutputFormat::Ndjson => {
+            let records = convert_results_to_records(&results);
+            if !records.is_empty() {
+                write_to_stdout(format_args!("{}\n", format_as_ndjson(&records)));
+            }
+        }
+        OutputFormat::Csv => write_to_stdout(format_args!(
+            "{}",
+            format_as_csv(&convert_results_to_records(&results), b',')
+        )),
+        OutputFormat::Tsv => write_to_stdout(format_args!(
+            "{}",
+            format_as_csv(&convert_results_to_records(&results), b'\t')
+        )),
+        OutputFormat::Table => {
+            let records = convert_results_to_records(&results);
+            page_or_print(&format_as_table(&records), cli);
+        }
+        OutputFormat::Text => {
+            let mut formatted_results =
+                format_as_text(&results, cli.is_output_colorized, cli.is_detailed);
 
-fn render(mut results: Vec<String>, cli: &CLI) {
-    if !cli.is_paging_disabled && cli.name.is_some() {
-        results.insert(0, format!(">>> {} results found", results.len()));
+            if !cli.is_paging_disabled && cli.chars.is_empty() && cli.string.is_none() {
+                let header = if !results.is_empty() && results.len() < result_count {
+                    format!(
+                        ">>> {} results found, showing {} to {}",
+                        result_count,
+                        cli.offset + 1,
+                        cli.offset + results.len()
+                    )
+                } else {
+                    format!(">>> {} results found", result_count)
+                };
+                formatted_results.insert(0, header);
+            }
+
+            page_or_print(&formatted_results.join("\n\n"), cli);
+        }
     }
+}
 
-    let result = results.join("\n\n");
+/// Writes to standard output like `print!`, but exits quietly instead of panicking
+/// once the reading end of a pipe has been closed, e.g. by `head`.
+fn write_to_stdout(arguments: Arguments) {
+    if let Err(error) = io::stdout().lock().write_fmt(arguments) {
+        if error.kind() == io::ErrorKind::BrokenPipe {
+            exit(0);
+        }
+        panic!("failed printing to stdout: {}", error);
+    }
+}
 
+fn page_or_print(output_text: &str, cli: &Cli) {
     if cli.is_paging_disabled {
-        println!("{}", result);
+        write_to_stdout(format_args!("{}\n", output_text));
     } else {
         let mut output = minus::Pager::new();
-        writeln!(output.lines, "{}", result)
+        writeln!(output.lines, "{}", output_text)
             .expect("Terminal output could not be written to pager");
         minus::page_all(output).expect("Pager could not be initialized");
     }
@@ -0,0 +1,91 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fmt::{Display, Formatter, Result};
+
+/// The reason why a code point is not contained in the database.
+pub enum MissingReason {
+    Noncharacter,
+    NewerVersion {
+        age: String,
+        database_version: String,
+    },
+    Unassigned,
+}
+
+impl MissingReason {
+    /// Determines why the code point is missing in a database built from
+    /// the given Unicode version. Code points assigned in newer versions are
+    /// detected with the ages of the newest Unicode version known at build time.
+    pub fn of(database: &Connection, codepoint: u32, database_version: &str) -> Self {
+        if is_noncharacter(codepoint) {
+            return MissingReason::Noncharacter;
+        }
+
+        if let Some(age) = find_newer_age(database, codepoint) {
+            return MissingReason::NewerVersion {
+                age,
+                database_version: database_version.to_string(),
+            };
+        }
+
+        MissingReason::Unassigned
+    }
+
+    /// Returns the code point label of section 4.8 of the Unicode Standard,
+    /// such as `<reserved-0378>`, which stands in for the missing name.
+    pub fn label(&self, codepoint: u32) -> String {
+        let label_type = match self {
+            MissingReason::Noncharacter => "noncharacter",
+            MissingReason::NewerVersion { .. } | MissingReason::Unassigned => "reserved",
+        };
+
+        format!("<{}-{:04X}>", label_type, codepoint)
+    }
+}
+
+impl Display for MissingReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            MissingReason::Noncharacter => write!(f, "noncharacter"),
+            MissingReason::NewerVersion {
+                age,
+                database_version,
+            } => write!(
+                f,
+                "assigned in Unicode {}, newer than the database's Unicode {}",
+                age, database_version
+            ),
+            MissingReason::Unassigned => write!(f, "unassigned code point"),
+        }
+    }
+}
+
+fn is_noncharacter(codepoint: u32) -> bool {
+    (0xFDD0..=0xFDEF).contains(&codepoint) || codepoint & 0xFFFE == 0xFFFE
+}
+
+fn find_newer_age(database: &Connection, codepoint: u32) -> Option<String> {
+    database
+        .query_row(
+            "SELECT age FROM NewerAges WHERE ? BETWEEN first_codepoint AND last_codepoint",
+            params![codepoint],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap()
+}
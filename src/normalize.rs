@@ -0,0 +1,277 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::decomposition::parse_decomposition_mapping;
+use rusqlite::{Connection, NO_PARAMS};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+#[derive(Clone, Copy)]
+pub enum NormalizationForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl NormalizationForm {
+    fn is_compatibility(&self) -> bool {
+        matches!(self, NormalizationForm::Nfkc | NormalizationForm::Nfkd)
+    }
+
+    fn is_composed(&self) -> bool {
+        matches!(self, NormalizationForm::Nfc | NormalizationForm::Nfkc)
+    }
+}
+
+impl FromStr for NormalizationForm {
+    type Err = String;
+
+    fn from_str(form: &str) -> Result<Self, Self::Err> {
+        match form.to_ascii_uppercase().as_str() {
+            "NFC" => Ok(NormalizationForm::Nfc),
+            "NFD" => Ok(NormalizationForm::Nfd),
+            "NFKC" => Ok(NormalizationForm::Nfkc),
+            "NFKD" => Ok(NormalizationForm::Nfkd),
+            _ => Err(format!(
+                "unknown normalization form '{}', expected NFC, NFD, NFKC or NFKD",
+                form
+            )),
+        }
+    }
+}
+
+impl Display for NormalizationForm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let form = match self {
+            NormalizationForm::Nfc => "NFC",
+            NormalizationForm::Nfd => "NFD",
+            NormalizationForm::Nfkc => "NFKC",
+            NormalizationForm::Nfkd => "NFKD",
+        };
+        write!(f, "{}", form)
+    }
+}
+
+/// A piece of text that is normalized independently of the text around it,
+/// located by its line and column, both starting at 1.
+pub struct NormalizationSegment {
+    pub line: usize,
+    pub column: usize,
+    pub original: Vec<u32>,
+    pub normalized: Vec<u32>,
+}
+
+impl NormalizationSegment {
+    pub fn is_changed(&self) -> bool {
+        self.original != self.normalized
+    }
+}
+
+/// Normalizes text as described in UAX #15 with the decompositions, combining classes
+/// and composition exclusions of the database, so that the results match its Unicode version.
+pub struct Normalizer {
+    decompositions: HashMap<u32, (bool, Vec<u32>)>,
+    combining_classes: HashMap<u32, u8>,
+    compositions: HashMap<(u32, u32), u32>,
+    second_composition_parts: HashSet<u32>,
+}
+
+impl Normalizer {
+    pub fn load(database: &Connection) -> Self {
+        let mut normalizer = Self {
+            decompositions: HashMap::new(),
+            combining_classes: HashMap::new(),
+            compositions: HashMap::new(),
+            second_composition_parts: HashSet::new(),
+        };
+        let mut statement = database
+            .prepare(
+                "SELECT codepoint, combining_class, decomposition, is_composition_excluded \
+                 FROM UnicodeData WHERE combining_class != 0 OR decomposition IS NOT NULL",
+            )
+            .unwrap();
+        let mut rows = statement.query(NO_PARAMS).unwrap();
+
+        while let Some(row) = rows.next().unwrap() {
+            let codepoint: u32 = row.get_unwrap(0);
+            let combining_class: u8 = row.get_unwrap(1);
+            let mapping: Option<String> = row.get_unwrap(2);
+            let is_composition_excluded: bool = row.get_unwrap(3);
+
+            if combining_class != 0 {
+                normalizer
+                    .combining_classes
+                    .insert(codepoint, combining_class);
+            }
+
+            if let Some(mapping) = mapping {
+                let (tag, codepoints) = parse_decomposition_mapping(&mapping);
+
+                if let (None, [first, second], false) =
+                    (&tag, codepoints.as_slice(), is_composition_excluded)
+                {
+                    normalizer.compositions.insert((*first, *second), codepoint);
+                    normalizer.second_composition_parts.insert(*second);
+                }
+
+                normalizer
+                    .decompositions
+                    .insert(codepoint, (tag.is_some(), codepoints));
+            }
+        }
+
+        normalizer
+    }
+
+    pub fn normalize(&self, text: &str, form: NormalizationForm) -> String {
+        self.split_into_segments(text, form)
+            .iter()
+            .flat_map(|segment| &segment.normalized)
+            .filter_map(|codepoint| char::from_u32(*codepoint))
+            .collect()
+    }
+
+    /// Splits text before each code point that cannot interact with the preceding ones
+    /// during normalization and normalizes the resulting segments.
+    pub fn split_into_segments(
+        &self,
+        text: &str,
+        form: NormalizationForm,
+    ) -> Vec<NormalizationSegment> {
+        let mut segments: Vec<NormalizationSegment> = vec![];
+        let mut line = 1;
+        let mut column = 1;
+
+        for c in text.chars() {
+            let codepoint = c as u32;
+
+            match segments.last_mut() {
+                Some(segment) if !self.has_boundary_before(codepoint, form) => {
+                    segment.original.push(codepoint)
+                }
+                _ => segments.push(NormalizationSegment {
+                    line,
+                    column,
+                    original: vec![codepoint],
+                    normalized: vec![],
+                }),
+            }
+
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        for segment in segments.iter_mut() {
+            segment.normalized = self.normalize_codepoints(&segment.original, form);
+        }
+
+        segments
+    }
+
+    fn normalize_codepoints(&self, codepoints: &[u32], form: NormalizationForm) -> Vec<u32> {
+        let mut decomposition = vec![];
+
+        for codepoint in codepoints {
+            self.decompose(*codepoint, form.is_compatibility(), &mut decomposition);
+        }
+
+        self.reorder_canonically(&mut decomposition);
+
+        if form.is_composed() {
+            self.compose(&decomposition)
+        } else {
+            decomposition
+        }
+    }
+
+    fn decompose(&self, codepoint: u32, is_compatibility: bool, decomposition: &mut Vec<u32>) {
+        match self.decompositions.get(&codepoint) {
+            Some((is_compatibility_mapping, mapping))
+                if is_compatibility || !is_compatibility_mapping =>
+            {
+                for mapped_codepoint in mapping {
+                    self.decompose(*mapped_codepoint, is_compatibility, decomposition);
+                }
+            }
+            _ => decomposition.push(codepoint),
+        }
+    }
+
+    /// Sorts each run of non-starters stably by their combining classes.
+    fn reorder_canonically(&self, codepoints: &mut [u32]) {
+        for run in codepoints.split_mut(|codepoint| self.combining_class(*codepoint) == 0) {
+            run.sort_by_key(|codepoint| self.combining_class(*codepoint));
+        }
+    }
+
+    /// Combines each starter with the following characters that are not blocked from it
+    /// and for which a primary composite exists.
+    fn compose(&self, codepoints: &[u32]) -> Vec<u32> {
+        let mut composition: Vec<u32> = vec![];
+        let mut starter_idx = None;
+
+        for codepoint in codepoints {
+            let combining_class = self.combining_class(*codepoint);
+
+            if let Some(starter_idx) = starter_idx {
+                let last_idx = composition.len() - 1;
+                let last_combining_class = self.combining_class(composition[last_idx]);
+                let is_blocked = last_idx != starter_idx
+                    && (last_combining_class == 0 || last_combining_class >= combining_class);
+
+                if !is_blocked {
+                    let starter: &mut u32 = &mut composition[starter_idx];
+
+                    if let Some(composite) = self.compositions.get(&(*starter, *codepoint)) {
+                        *starter = *composite;
+                        continue;
+                    }
+                }
+            }
+
+            if combining_class == 0 {
+                starter_idx = Some(composition.len());
+            }
+
+            composition.push(*codepoint);
+        }
+
+        composition
+    }
+
+    fn has_boundary_before(&self, codepoint: u32, form: NormalizationForm) -> bool {
+        let mut decomposition = vec![];
+        self.decompose(codepoint, form.is_compatibility(), &mut decomposition);
+
+        let first_codepoint = decomposition[0];
+
+        self.combining_class(first_codepoint) == 0
+            && !(form.is_composed() && self.second_composition_parts.contains(&first_codepoint))
+    }
+
+    fn combining_class(&self, codepoint: u32) -> u8 {
+        self.combining_classes
+            .get(&codepoint)
+            .copied()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use rusqlite::{Connection, NO_PARAMS};
+use std::collections::HashMap;
+use strum_macros::{AsRefStr, EnumString};
+
+#[derive(AsRefStr, EnumString, Clone, Copy)]
+pub enum NumericType {
+    De,
+    Di,
+    Nu,
+}
+
+impl NumericType {
+    pub fn description(&self) -> &'static str {
+        match self {
+            NumericType::De => "Decimal",
+            NumericType::Di => "Digit",
+            NumericType::Nu => "Numeric",
+        }
+    }
+}
+
+/// Writes a numeric value such as `1/2` or `-1/2` as a decimal number,
+/// unless it already is an integer.
+pub fn format_rational_value(numeric_value: &str) -> Option<String> {
+    let (numerator, denominator) = numeric_value.split_once('/')?;
+    let numerator = numerator.parse::<f64>().ok()?;
+    let denominator = denominator.parse::<f64>().ok()?;
+
+    Some((numerator / denominator).to_string())
+}
+
+struct DecimalDigit {
+    value: u8,
+    name: String,
+}
+
+/// Parses strings of decimal digits of any script. As required by UTS #39,
+/// all digits of a string must belong to the same sequence of ten digits
+/// from zero to nine, so that mixing ASCII and Devanagari digits is rejected.
+pub struct DigitParser {
+    digits: HashMap<char, DecimalDigit>,
+}
+
+impl DigitParser {
+    pub fn load(database: &Connection) -> Self {
+        let mut statement = database
+            .prepare(
+                "SELECT codepoint, name, numeric_value FROM UnicodeData \
+                 WHERE numeric_type = 'De'",
+            )
+            .unwrap();
+        let mut rows = statement.query(NO_PARAMS).unwrap();
+        let mut digits = HashMap::new();
+
+        while let Some(row) = rows.next().unwrap() {
+            let codepoint: u32 = row.get_unwrap(0);
+            let numeric_value: String = row.get_unwrap(2);
+
+            if let Some(c) = std::char::from_u32(codepoint) {
+                digits.insert(
+                    c,
+                    DecimalDigit {
+                        value: numeric_value.parse().unwrap(),
+                        name: row.get_unwrap(1),
+                    },
+                );
+            }
+        }
+
+        Self { digits }
+    }
+
+    /// Returns the ASCII digits of the integer written in `text`
+    /// without leading zeros, so that `१२३` and `٠٠١٢٣` both result in `123`.
+    pub fn parse(&self, text: &str) -> Result<String, String> {
+        let mut zero_codepoint = None;
+        let mut first_digit = None;
+        let mut ascii_digits = String::new();
+
+        for c in text.chars() {
+            let digit = self
+                .digits
+                .get(&c)
+                .ok_or_else(|| format!("'{}' U+{:04X} is not a decimal digit", c, c as u32))?;
+            let digit_zero_codepoint = c as u32 - digit.value as u32;
+
+            match zero_codepoint {
+                None => {
+                    zero_codepoint = Some(digit_zero_codepoint);
+                    first_digit = Some((c, digit));
+                }
+                Some(zero_codepoint) if zero_codepoint != digit_zero_codepoint => {
+                    let (first_char, first_digit) = first_digit.unwrap();
+                    return Err(format!(
+                        "'{}' mixes digits of different numbering systems, \
+                         such as U+{:04X} {} and U+{:04X} {}",
+                        text, first_char as u32, first_digit.name, c as u32, digit.name
+                    ));
+                }
+                Some(_) => {}
+            }
+
+            ascii_digits.push((b'0' + digit.value) as char);
+        }
+
+        if ascii_digits.is_empty() {
+            return Err(String::from("expected at least one decimal digit"));
+        }
+
+        let integer = ascii_digits.trim_start_matches('0');
+
+        Ok(if integer.is_empty() {
+            String::from("0")
+        } else {
+            integer.to_string()
+        })
+    }
+}
@@ -0,0 +1,1108 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bidi::{BidiParagraph, Direction};
+use crate::case::CaseMapping;
+use crate::character::{Annotation, NameAlias, SearchResults, UnicodeCharacter};
+use crate::decomposition::{combining_class_name, Decomposition};
+use crate::normalize::NormalizationSegment;
+use crate::numeric::format_rational_value;
+use colored::Colorize;
+use csv::WriterBuilder;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::iter;
+use strum_macros::{EnumString, EnumVariantNames};
+use unicode_width::UnicodeWidthStr;
+
+#[derive(EnumString, EnumVariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+    Tsv,
+}
+
+/// The fields of a single character that all output formats are built from.
+#[derive(Serialize)]
+pub struct CharacterRecord<'a> {
+    #[serde(rename = "char")]
+    pub glyph: String,
+    pub codepoint: u32,
+    pub hex: String,
+    pub name: &'a str,
+    pub category: &'a str,
+    pub category_description: &'static str,
+    pub block: &'a str,
+    pub age: &'a str,
+    pub aliases: Vec<AliasRecord<'a>>,
+    pub unicode_1_name: Option<&'a str>,
+    pub iso_comment: Option<&'a str>,
+    pub combining_class: u8,
+    pub decomposition: Option<DecompositionRecord<'a>>,
+    pub uppercase: Option<CaseMappingRecord>,
+    pub lowercase: Option<CaseMappingRecord>,
+    pub titlecase: Option<CaseMappingRecord>,
+    pub case_folding: Option<CaseMappingRecord>,
+    pub bidi_class: &'a str,
+    pub bidi_class_description: &'static str,
+    pub bidi_class_type: &'static str,
+    pub bidi_mirrored: bool,
+    pub bidi_mirroring_glyph: Option<CodepointRecord>,
+    pub bidi_paired_bracket: Option<PairedBracketRecord>,
+    pub numeric_type: Option<&'static str>,
+    pub numeric_value: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<AnnotationRecord<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decomposition_group: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grapheme_cluster: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct AliasRecord<'a> {
+    pub alias: &'a str,
+    #[serde(rename = "type")]
+    pub alias_type: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct DecompositionRecord<'a> {
+    #[serde(rename = "type")]
+    pub decomposition_type: &'a str,
+    pub components: Vec<ComponentRecord<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct ComponentRecord<'a> {
+    #[serde(rename = "char")]
+    pub glyph: String,
+    pub hex: String,
+    pub name: &'a str,
+    pub decomposition: Option<DecompositionRecord<'a>>,
+}
+
+/// The texts a character is mapped to, which is the character itself
+/// for a simple or full mapping that leaves it unchanged.
+#[derive(Serialize)]
+pub struct CaseMappingRecord {
+    pub full: String,
+    pub simple: String,
+}
+
+#[derive(Serialize)]
+pub struct CodepointRecord {
+    #[serde(rename = "char")]
+    pub glyph: String,
+    pub hex: String,
+}
+
+#[derive(Serialize)]
+pub struct PairedBracketRecord {
+    #[serde(rename = "char")]
+    pub glyph: String,
+    pub hex: String,
+    /// Either `open` or `close`.
+    #[serde(rename = "type")]
+    pub bracket_type: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct AnnotationRecord<'a> {
+    pub text: &'a str,
+    #[serde(rename = "type")]
+    pub annotation_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+impl<'a> CharacterRecord<'a> {
+    fn from(character: &'a UnicodeCharacter, grapheme_cluster: Option<usize>) -> Self {
+        Self {
+            glyph: character.glyph(),
+            codepoint: character.codepoint,
+            hex: character.hex_code(),
+            name: &character.name,
+            category: &character.category,
+            category_description: character.category_description(),
+            block: &character.block,
+            age: &character.age,
+            aliases: character.aliases.iter().map(AliasRecord::from).collect(),
+            unicode_1_name: character.unicode_1_name.as_deref(),
+            iso_comment: character.iso_comment.as_deref(),
+            combining_class: character.combining_class,
+            decomposition: character
+                .decomposition
+                .as_ref()
+                .map(DecompositionRecord::from),
+            uppercase: CaseMappingRecord::from(&character.uppercase, character),
+            lowercase: CaseMappingRecord::from(&character.lowercase, character),
+            titlecase: CaseMappingRecord::from(&character.titlecase, character),
+            case_folding: CaseMappingRecord::from(&character.case_folding, character),
+            bidi_class: &character.bidi_class,
+            bidi_class_description: character.bidi_class().description(),
+            bidi_class_type: character.bidi_class().class_type(),
+            bidi_mirrored: character.is_bidi_mirrored,
+            bidi_mirroring_glyph: character.bidi_mirroring_glyph.map(CodepointRecord::from),
+            bidi_paired_bracket: character.bidi_paired_bracket.map(|codepoint| {
+                let codepoint_record = CodepointRecord::from(codepoint);
+                PairedBracketRecord {
+                    glyph: codepoint_record.glyph,
+                    hex: codepoint_record.hex,
+                    bracket_type: match character.bidi_paired_bracket_type.as_deref() {
+                        Some("o") => "open",
+                        _ => "close",
+                    },
+                }
+            }),
+            numeric_type: character
+                .numeric_type()
+                .map(|numeric_type| numeric_type.description()),
+            numeric_value: character.numeric_value.as_deref(),
+            annotations: character
+                .annotations
+                .as_ref()
+                .map(|annotations| annotations.iter().map(AnnotationRecord::from).collect()),
+            decomposition_group: character.decomposition_group.as_deref(),
+            grapheme_cluster,
+            score: character.score,
+        }
+    }
+
+    /// Returns the case mappings along with the names of their fields.
+    fn case_mappings(&self) -> [(&'static str, &Option<CaseMappingRecord>); 4] {
+        [
+            ("uppercase", &self.uppercase),
+            ("lowercase", &self.lowercase),
+            ("titlecase", &self.titlecase),
+            ("case_folding", &self.case_folding),
+        ]
+    }
+}
+
+impl<'a> AliasRecord<'a> {
+    fn from(name_alias: &'a NameAlias) -> Self {
+        Self {
+            alias: &name_alias.alias,
+            alias_type: &name_alias.alias_type,
+        }
+    }
+}
+
+impl<'a> DecompositionRecord<'a> {
+    fn from(decomposition: &'a Decomposition) -> Self {
+        Self {
+            decomposition_type: decomposition.decomposition_type(),
+            components: decomposition
+                .components
+                .iter()
+                .map(|component| ComponentRecord {
+                    glyph: char::from_u32(component.codepoint)
+                        .map(String::from)
+                        .unwrap_or_default(),
+                    hex: format!("U+{:04X}", component.codepoint),
+                    name: &component.name,
+                    decomposition: component
+                        .decomposition
+                        .as_ref()
+                        .map(DecompositionRecord::from),
+                })
+                .collect(),
+        }
+    }
+
+    fn tag(&self) -> Option<&str> {
+        Some(self.decomposition_type).filter(|tag| *tag != "canonical")
+    }
+}
+
+impl CaseMappingRecord {
+    fn from(case_mapping: &CaseMapping, character: &UnicodeCharacter) -> Option<Self> {
+        if case_mapping.is_identity() {
+            return None;
+        }
+
+        let glyph = character.glyph();
+
+        Some(Self {
+            full: case_mapping.full.clone().unwrap_or_else(|| glyph.clone()),
+            simple: case_mapping.simple.clone().unwrap_or(glyph),
+        })
+    }
+}
+
+impl CodepointRecord {
+    fn from(codepoint: u32) -> Self {
+        Self {
+            glyph: char::from_u32(codepoint)
+                .map(String::from)
+                .unwrap_or_default(),
+            hex: format!("U+{:04X}", codepoint),
+        }
+    }
+}
+
+impl<'a> AnnotationRecord<'a> {
+    fn from(annotation: &'a Annotation) -> Self {
+        Self {
+            text: &annotation.text,
+            annotation_type: &annotation.annotation_type,
+            reference: annotation
+                .reference
+                .map(|codepoint| format!("U+{:04X}", codepoint)),
+        }
+    }
+}
+
+/// Formats aliases such as `LINE FEED (control), LF (abbreviation)`.
+pub fn format_aliases(aliases: &[AliasRecord]) -> String {
+    aliases
+        .iter()
+        .map(|alias| format!("{} ({})", alias.alias, alias.alias_type))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats the mapping of a decomposition such as `<noBreak> U+0020`.
+pub fn format_decomposition(decomposition: &DecompositionRecord) -> String {
+    decomposition
+        .tag()
+        .map(|tag| format!("<{}>", tag))
+        .into_iter()
+        .chain(
+            decomposition
+                .components
+                .iter()
+                .map(|component| component.hex.clone()),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats a case mapping such as `SS (simple ß)`,
+/// naming the simple mapping only if it differs from the full one.
+pub fn format_case_mapping(case_mapping: &CaseMappingRecord) -> String {
+    if case_mapping.simple == case_mapping.full {
+        case_mapping.full.clone()
+    } else {
+        format!("{} (simple {})", case_mapping.full, case_mapping.simple)
+    }
+}
+
+pub fn convert_results_to_records(results: &SearchResults) -> Vec<CharacterRecord<'_>> {
+    match results {
+        SearchResults::Characters(characters) => characters
+            .iter()
+            .map(|character| CharacterRecord::from(character, None))
+            .collect(),
+        SearchResults::GraphemeClusters(grapheme_clusters) => grapheme_clusters
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, grapheme_cluster)| {
+                grapheme_cluster
+                    .characters
+                    .iter()
+                    .map(move |character| CharacterRecord::from(character, Some(idx + 1)))
+            })
+            .collect(),
+    }
+}
+
+pub fn format_as_text(
+    results: &SearchResults,
+    is_output_colorized: bool,
+    is_detailed: bool,
+) -> Vec<String> {
+    match results {
+        SearchResults::Characters(characters) => {
+            let mut formatted_characters = vec![];
+            let mut current_group = None;
+
+            for (idx, character) in characters.iter().enumerate() {
+                let record = CharacterRecord::from(character, None);
+
+                // Variants are listed below a heading for each group of decompositions.
+                if record.decomposition_group.is_some()
+                    && record.decomposition_group != current_group
+                {
+                    current_group = record.decomposition_group;
+                    formatted_characters.push(format_group_heading(
+                        current_group.unwrap_or_default(),
+                        is_output_colorized,
+                    ));
+                }
+
+                formatted_characters.push(format_record_as_text(
+                    &record,
+                    &format!("{}.", idx + 1),
+                    is_output_colorized,
+                    is_detailed,
+                ));
+            }
+
+            formatted_characters
+        }
+        SearchResults::GraphemeClusters(grapheme_clusters) => grapheme_clusters
+            .iter()
+            .enumerate()
+            .map(|(cluster_idx, grapheme_cluster)| {
+                let cluster_label = format!("{}.", cluster_idx + 1);
+                let hex_codes = grapheme_cluster
+                    .text
+                    .chars()
+                    .map(|c| format!("U+{:04X}", c as u32))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let mut cluster_results = vec![if is_output_colorized {
+                    format!(
+                        "{}\t{}\t{}",
+                        cluster_label.as_str().bright_white().on_bright_blue(),
+                        grapheme_cluster.text,
+                        hex_codes.as_str().green()
+                    )
+                } else {
+                    format!(
+                        "{}\t{}\t{}",
+                        cluster_label, grapheme_cluster.text, hex_codes
+                    )
+                }];
+
+                for (char_idx, character) in grapheme_cluster.characters.iter().enumerate() {
+                    let record = CharacterRecord::from(character, Some(cluster_idx + 1));
+                    let label = format!("{}.{}.", cluster_idx + 1, char_idx + 1);
+                    cluster_results.push(format_record_as_text(
+                        &record,
+                        &label,
+                        is_output_colorized,
+                        is_detailed,
+                    ));
+                }
+
+                cluster_results.join("\n\n")
+            })
+            .collect(),
+    }
+}
+
+fn format_record_as_text(
+    record: &CharacterRecord,
+    label: &str,
+    is_output_colorized: bool,
+    is_detailed: bool,
+) -> String {
+    let formatted_other_names = format_other_names(record);
+    let mut formatted_annotations = format_annotations(record, is_output_colorized);
+
+    if is_detailed {
+        formatted_annotations.push_str(&format_combining_class(record.combining_class));
+
+        if let Some(decomposition) = &record.decomposition {
+            formatted_annotations.push_str(&format_decomposition_tree(decomposition));
+        }
+
+        formatted_annotations.push_str(&format_case_mappings(record));
+        formatted_annotations.push_str(&format_bidi_properties(record));
+        formatted_annotations.push_str(&format_numeric_value(record));
+    }
+    let formatted_age = format!("since {}", record.age);
+    let formatted_score = record
+        .score
+        .map(|score| format!("\nscore {}", score))
+        .unwrap_or_default();
+
+    if is_output_colorized {
+        let colored_label = label.bright_white().on_bright_blue();
+        let colored_hex_code = record.hex.as_str().green();
+        let colored_name = record.name.cyan();
+        let colored_other_names = formatted_other_names.as_str().cyan();
+        let colored_category = record.category_description.red();
+        let colored_block = record.block.purple();
+        let colored_age = formatted_age.as_str().yellow();
+        let colored_score = formatted_score.as_str().blue();
+
+        format!(
+            "{}\t{}\t{}\n{}{}{}\n{}\t{}\n{}{}",
+            colored_label,
+            record.glyph,
+            colored_hex_code,
+            colored_name,
+            colored_other_names,
+            formatted_annotations,
+            colored_block,
+            colored_category,
+            colored_age,
+            colored_score
+        )
+    } else {
+        format!(
+            "{}\t{}\t{}\n{}{}{}\n{}\t{}\n{}{}",
+            label,
+            record.glyph,
+            record.hex,
+            record.name,
+            formatted_other_names,
+            formatted_annotations,
+            record.block,
+            record.category_description,
+            formatted_age,
+            formatted_score
+        )
+    }
+}
+
+fn format_group_heading(decomposition_group: &str, is_output_colorized: bool) -> String {
+    let heading = if decomposition_group == "canonical" {
+        String::from("--- canonical decomposition ---")
+    } else {
+        format!(
+            "--- compatibility decomposition <{}> ---",
+            decomposition_group
+        )
+    };
+
+    if is_output_colorized {
+        heading.bold().to_string()
+    } else {
+        heading
+    }
+}
+
+fn format_other_names(record: &CharacterRecord) -> String {
+    let mut other_names = String::new();
+
+    if !record.aliases.is_empty() {
+        other_names.push_str(&format!(
+            "\nalso known as {}",
+            format_aliases(&record.aliases)
+        ));
+    }
+
+    if let Some(unicode_1_name) = record.unicode_1_name {
+        other_names.push_str(&format!("\nUnicode 1.0 name {}", unicode_1_name));
+    }
+
+    if let Some(iso_comment) = record.iso_comment {
+        other_names.push_str(&format!("\nISO comment {}", iso_comment));
+    }
+
+    other_names
+}
+
+/// Formats annotations the way the Unicode code charts do. Referenced code points
+/// are written as chr accepts them, so they can be looked up right away, and in
+/// colorized output they are hyperlinks in terminals supporting them.
+fn format_annotations(record: &CharacterRecord, is_output_colorized: bool) -> String {
+    let mut formatted_annotations = String::new();
+
+    for annotation in record.annotations.iter().flatten() {
+        let formatted_annotation = match (annotation.annotation_type, &annotation.reference) {
+            ("informal_alias", _) => format!("= {}", annotation.text),
+            ("note", _) => format!("• {}", annotation.text),
+            ("compatibility_decomposition", _) => {
+                format!("≈ {}", format_codepoints(annotation.text))
+            }
+            (_, Some(reference)) if is_output_colorized => format!(
+                "→ {} {}",
+                format_hyperlink(reference, &codepoint_url(reference))
+                    .as_str()
+                    .green(),
+                annotation.text
+            ),
+            (_, Some(reference)) => format!("→ {} {}", reference, annotation.text),
+            _ => continue,
+        };
+
+        formatted_annotations.push('\n');
+        formatted_annotations.push_str(&formatted_annotation);
+    }
+
+    formatted_annotations
+}
+
+fn format_combining_class(combining_class: u8) -> String {
+    match combining_class_name(combining_class) {
+        Some(name) => format!("\ncombining class {} ({})", combining_class, name),
+        None => format!("\ncombining class {}", combining_class),
+    }
+}
+
+/// Draws a decomposition as a tree whose branches are the decompositions of its components,
+/// with the tags of compatibility decompositions written after the components they belong to.
+fn format_decomposition_tree(decomposition: &DecompositionRecord) -> String {
+    let mut tree = match decomposition.tag() {
+        Some(tag) => format!("\ncompatibility decomposition <{}>", tag),
+        None => String::from("\ncanonical decomposition"),
+    };
+
+    format_decomposition_branches(decomposition, "", &mut tree);
+
+    tree
+}
+
+fn format_decomposition_branches(
+    decomposition: &DecompositionRecord,
+    indent: &str,
+    tree: &mut String,
+) {
+    let component_count = decomposition.components.len();
+
+    for (idx, component) in decomposition.components.iter().enumerate() {
+        let is_last_component = idx + 1 == component_count;
+        let (branch, nested_indent) = if is_last_component {
+            ("└─", format!("{}   ", indent))
+        } else {
+            ("├─", format!("{}│  ", indent))
+        };
+        let tag = component
+            .decomposition
+            .as_ref()
+            .and_then(|decomposition| decomposition.tag())
+            .map(|tag| format!(" <{}>", tag))
+            .unwrap_or_default();
+
+        tree.push_str(&format!(
+            "\n{}{} {} {} {}{}",
+            indent, branch, component.hex, component.glyph, component.name, tag
+        ));
+
+        if let Some(decomposition) = &component.decomposition {
+            format_decomposition_branches(decomposition, &nested_indent, tree);
+        }
+    }
+}
+
+/// Lists the case mappings that change the character, with the simple mapping
+/// in parentheses if it differs from the full one, such as the simple uppercase
+/// mapping of ß that leaves it unchanged whereas the full one results in SS.
+fn format_case_mappings(record: &CharacterRecord) -> String {
+    let format_text = |text: &str| {
+        iter::once(text.to_string())
+            .chain(text.chars().map(|c| format!("U+{:04X}", c as u32)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut formatted_case_mappings = String::new();
+
+    for (field, case_mapping) in record.case_mappings().iter() {
+        if let Some(case_mapping) = case_mapping {
+            formatted_case_mappings.push_str(&format!(
+                "\n{} → {}",
+                field.replace('_', " "),
+                format_text(&case_mapping.full)
+            ));
+
+            if case_mapping.simple != case_mapping.full {
+                formatted_case_mappings
+                    .push_str(&format!(" (simple {})", format_text(&case_mapping.simple)));
+            }
+        }
+    }
+
+    formatted_case_mappings
+}
+
+/// Describes how a character behaves in bidirectional text: its bidi class together
+/// with the type of the class, its mirror image in right-to-left text, if any,
+/// and the bracket it is paired with.
+fn format_bidi_properties(record: &CharacterRecord) -> String {
+    let mut formatted_properties = format!(
+        "\nbidi class {} {} ({})",
+        record.bidi_class, record.bidi_class_description, record.bidi_class_type
+    );
+
+    match &record.bidi_mirroring_glyph {
+        Some(mirroring_glyph) => formatted_properties.push_str(&format!(
+            "\nmirrored → {} {}",
+            mirroring_glyph.glyph, mirroring_glyph.hex
+        )),
+        None if record.bidi_mirrored => {
+            formatted_properties.push_str("\nmirrored without a mirroring glyph")
+        }
+        None => {}
+    }
+
+    if let Some(paired_bracket) = &record.bidi_paired_bracket {
+        let bracket_type = if paired_bracket.bracket_type == "open" {
+            "opening"
+        } else {
+            "closing"
+        };
+        formatted_properties.push_str(&format!(
+            "\n{} bracket paired with {} {}",
+            bracket_type, paired_bracket.glyph, paired_bracket.hex
+        ));
+    }
+
+    formatted_properties
+}
+
+/// Describes the numeric value of a character together with its numeric type,
+/// adding the decimal notation of fractions such as `1/2 = 0.5`.
+fn format_numeric_value(record: &CharacterRecord) -> String {
+    match (record.numeric_value, record.numeric_type) {
+        (Some(numeric_value), Some(numeric_type)) => {
+            let formatted_value = match format_rational_value(numeric_value) {
+                Some(decimal_value) => format!("{} = {}", numeric_value, decimal_value),
+                None => numeric_value.to_string(),
+            };
+            format!(
+                "\nnumeric value {} ({})",
+                formatted_value,
+                numeric_type.to_lowercase()
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+/// Writes the hexadecimal code points within a decomposition such as `<noBreak> 0020`
+/// in the U+ notation.
+fn format_codepoints(text: &str) -> String {
+    text.split(' ')
+        .map(|part| {
+            if part.len() >= 4 && part.chars().all(|c| c.is_ascii_hexdigit()) {
+                format!("U+{}", part)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn codepoint_url(hex_code: &str) -> String {
+    format!(
+        "https://util.unicode.org/UnicodeJsps/character.jsp?a={}",
+        hex_code.trim_start_matches("U+")
+    )
+}
+
+/// Wraps text into an OSC 8 escape sequence, which terminals
+/// that do not support it simply ignore.
+fn format_hyperlink(text: &str, url: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+pub fn format_as_json(records: &[CharacterRecord]) -> String {
+    serde_json::to_string_pretty(records).expect("Results could not be serialized to JSON")
+}
+
+pub fn format_as_ndjson(records: &[CharacterRecord]) -> String {
+    records
+        .iter()
+        .map(|record| {
+            serde_json::to_string(record).expect("Result could not be serialized to JSON")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_as_csv(records: &[CharacterRecord], delimiter: u8) -> String {
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(vec![]);
+
+    for (idx, record) in records.iter().enumerate() {
+        let mut fields = match serde_json::to_value(record) {
+            Ok(Value::Object(fields)) => fields,
+            _ => panic!("Result could not be serialized to CSV"),
+        };
+
+        // The decomposition tree would be unreadable within a single field,
+        // so only the mapping of the character itself is written.
+        if let Some(decomposition) = &record.decomposition {
+            fields.insert(
+                String::from("decomposition"),
+                Value::String(format_decomposition(decomposition)),
+            );
+        }
+
+        for (field, case_mapping) in record.case_mappings().iter() {
+            if let Some(case_mapping) = case_mapping {
+                fields.insert(
+                    field.to_string(),
+                    Value::String(format_case_mapping(case_mapping)),
+                );
+            }
+        }
+
+        if idx == 0 {
+            writer
+                .write_record(fields.keys())
+                .expect("CSV header could not be written");
+        }
+
+        writer
+            .write_record(fields.values().map(format_csv_value))
+            .expect("Result could not be serialized to CSV");
+    }
+
+    let bytes = writer
+        .into_inner()
+        .expect("CSV output could not be flushed");
+
+    String::from_utf8(bytes).expect("CSV output is not valid UTF-8")
+}
+
+/// Flattens a value into a single CSV field. Lists are separated by semicolons
+/// and nested objects such as aliases are written as their first field followed
+/// by the remaining ones in parentheses, such as `LF (abbreviation)`.
+fn format_csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(format_csv_value)
+            .collect::<Vec<_>>()
+            .join("; "),
+        Value::Object(fields) => {
+            let mut values = fields.values().map(format_csv_value);
+            let first_value = values.next().unwrap_or_default();
+            let remaining_values = values.collect::<Vec<_>>();
+
+            if remaining_values.is_empty() {
+                first_value
+            } else {
+                format!("{} ({})", first_value, remaining_values.join(", "))
+            }
+        }
+        _ => value.to_string(),
+    }
+}
+
+pub fn format_as_table(records: &[CharacterRecord]) -> String {
+    let has_grapheme_clusters = records
+        .iter()
+        .any(|record| record.grapheme_cluster.is_some());
+    let mut header = vec![
+        "No.",
+        "Char",
+        "Code Point",
+        "Name",
+        "Block",
+        "Category",
+        "Age",
+    ];
+
+    if has_grapheme_clusters {
+        header.push("Cluster");
+    }
+
+    if records
+        .iter()
+        .any(|record| record.decomposition_group.is_some())
+    {
+        header.push("Group");
+    }
+
+    if records.iter().any(|record| record.score.is_some()) {
+        header.push("Score");
+    }
+
+    let mut rows = vec![header
+        .iter()
+        .map(|cell| cell.to_string())
+        .collect::<Vec<_>>()];
+
+    for (idx, record) in records.iter().enumerate() {
+        // Control characters would break up the table layout, so they are not displayed.
+        let glyph = if record.category == "Cc" {
+            String::new()
+        } else {
+            record.glyph.clone()
+        };
+        let mut row = vec![
+            (idx + 1).to_string(),
+            glyph,
+            record.hex.clone(),
+            record.name.to_string(),
+            record.block.to_string(),
+            record.category_description.to_string(),
+            record.age.to_string(),
+        ];
+
+        if let Some(grapheme_cluster) = record.grapheme_cluster {
+            row.push(grapheme_cluster.to_string());
+        }
+
+        if let Some(decomposition_group) = record.decomposition_group {
+            row.push(decomposition_group.to_string());
+        }
+
+        if let Some(score) = record.score {
+            row.push(score.to_string());
+        }
+
+        rows.push(row);
+    }
+
+    let column_widths = (0..header.len())
+        .map(|column_idx| {
+            rows.iter()
+                .map(|row| row[column_idx].width())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(column_widths.iter())
+                .map(|(cell, column_width)| {
+                    format!("{}{}", cell, " ".repeat(column_width - cell.width()))
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_as_ranges(ranges: &[(u32, u32)]) -> Vec<String> {
+    ranges
+        .iter()
+        .map(|(start, end)| {
+            if start == end {
+                format!("U+{:04X}", start)
+            } else {
+                format!("U+{:04X}..U+{:04X}", start, end)
+            }
+        })
+        .collect()
+}
+
+/// Formats code points such as `U+0065 U+0301`.
+pub fn format_codepoint_sequence(codepoints: &[u32]) -> String {
+    codepoints
+        .iter()
+        .map(|codepoint| format!("U+{:04X}", codepoint))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Lists the code points of each segment before and after normalization side by side,
+/// headed by the location of the segment.
+pub fn format_normalization_segments(
+    segments: &[NormalizationSegment],
+    names: &HashMap<u32, String>,
+    location_prefix: &str,
+) -> String {
+    let format_codepoint = |codepoint: &u32| format!("U+{:04X} {}", codepoint, names[codepoint]);
+
+    segments
+        .iter()
+        .map(|segment| {
+            let original = segment
+                .original
+                .iter()
+                .map(format_codepoint)
+                .collect::<Vec<_>>();
+            let normalized = segment
+                .normalized
+                .iter()
+                .map(format_codepoint)
+                .collect::<Vec<_>>();
+            let column_width = original.iter().map(|line| line.width()).max().unwrap_or(0);
+            let mut lines = vec![format!(
+                "{}{}:{}",
+                location_prefix, segment.line, segment.column
+            )];
+
+            for idx in 0..original.len().max(normalized.len()) {
+                let left = original.get(idx).map(String::as_str).unwrap_or_default();
+                let right = normalized.get(idx).map(String::as_str).unwrap_or_default();
+                let arrow = if idx == 0 { "→" } else { " " };
+                let line = format!(
+                    "{}{}  {}  {}",
+                    left,
+                    " ".repeat(column_width - left.width()),
+                    arrow,
+                    right
+                );
+
+                lines.push(line.trim_end().to_string());
+            }
+
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Lists the resolved class and level of each character of each paragraph,
+/// followed by its level runs and its characters in display order.
+/// Characters that start or end explicit embeddings, overrides and isolates
+/// and characters whose class is overridden are highlighted.
+pub fn format_bidi_paragraphs(
+    paragraphs: &[BidiParagraph],
+    names: &HashMap<u32, String>,
+    is_output_colorized: bool,
+) -> String {
+    let highlight = |text: String| {
+        if is_output_colorized {
+            text.yellow().bold().to_string()
+        } else {
+            text
+        }
+    };
+    let heading = |text: &str| {
+        if is_output_colorized {
+            text.bold().to_string()
+        } else {
+            text.to_string()
+        }
+    };
+
+    paragraphs
+        .iter()
+        .enumerate()
+        .map(|(paragraph_idx, paragraph)| {
+            let position = |idx: usize| paragraph.offset + idx + 1;
+            let position_width = position(paragraph.characters.len()).to_string().len();
+            let visible_text = |indices: &mut dyn Iterator<Item = usize>| {
+                indices
+                    .filter_map(|idx| std::char::from_u32(paragraph.characters[idx].codepoint))
+                    .filter(|c| !c.is_control())
+                    .collect::<String>()
+            };
+            let classes = paragraph
+                .characters
+                .iter()
+                .map(|character| {
+                    let bidi_class = character.bidi_class.as_ref();
+                    let resolved_class = character.resolved_class.as_ref();
+
+                    if character.level.is_none() || bidi_class == resolved_class {
+                        bidi_class.to_string()
+                    } else {
+                        format!("{} → {}", bidi_class, resolved_class)
+                    }
+                })
+                .collect::<Vec<_>>();
+            let levels = paragraph
+                .characters
+                .iter()
+                .map(|character| {
+                    character
+                        .level
+                        .map(|level| format!("level {}", level))
+                        .unwrap_or_else(|| String::from("removed"))
+                })
+                .collect::<Vec<_>>();
+            let class_width = classes.iter().map(|class| class.width()).max().unwrap_or(0);
+            let level_width = levels.iter().map(|level| level.len()).max().unwrap_or(0);
+            let mut lines = vec![
+                heading(&format!(
+                    "Paragraph {} at level {} ({})",
+                    paragraph_idx + 1,
+                    paragraph.level,
+                    Direction::of_level(paragraph.level)
+                )),
+                String::new(),
+                heading("Levels"),
+            ];
+
+            for (idx, character) in paragraph.characters.iter().enumerate() {
+                let mut line = format!(
+                    "{:>position_width$}  U+{:04X}  {}{}  {:<level_width$}  {}",
+                    position(idx),
+                    character.codepoint,
+                    classes[idx],
+                    " ".repeat(class_width - classes[idx].width()),
+                    levels[idx],
+                    names[&character.codepoint],
+                    position_width = position_width,
+                    level_width = level_width
+                );
+
+                if let Some(formatting) = character.formatting {
+                    line.push_str("  ");
+                    line.push_str(&highlight(formatting.to_string()));
+                }
+                lines.push(line);
+            }
+
+            lines.push(String::new());
+            lines.push(heading("Runs"));
+
+            let run_positions = paragraph
+                .runs
+                .iter()
+                .map(|run| {
+                    if run.start == run.end {
+                        position(run.start).to_string()
+                    } else {
+                        format!("{}–{}", position(run.start), position(run.end))
+                    }
+                })
+                .collect::<Vec<_>>();
+            let run_positions_width = run_positions
+                .iter()
+                .map(|positions| positions.width())
+                .max()
+                .unwrap_or(0);
+
+            for (run, positions) in paragraph.runs.iter().zip(&run_positions) {
+                let text = visible_text(
+                    &mut (run.start..=run.end)
+                        .filter(|idx| paragraph.characters[*idx].level.is_some()),
+                );
+                let line = format!(
+                    "{}{}  level {}  {}  {}",
+                    positions,
+                    " ".repeat(run_positions_width - positions.width()),
+                    run.level,
+                    Direction::of_level(run.level),
+                    text
+                );
+
+                lines.push(line.trim_end().to_string());
+            }
+
+            lines.push(String::new());
+            lines.push(heading("Visual order"));
+            lines.push(
+                paragraph
+                    .visual_order
+                    .iter()
+                    .map(|idx| position(*idx).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+            lines.push(
+                paragraph
+                    .visual_text
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .collect(),
+            );
+
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
@@ -0,0 +1,341 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::iter::Peekable;
+use std::str::FromStr;
+use std::vec::IntoIter;
+
+/// A boolean query over character names such as `latin small AND acute NOT dot`.
+///
+/// Consecutive words form a single term that is matched as a phrase at the start
+/// of words in a name or alias, so its last word may be incomplete, just like a query
+/// without any operators. Such a term also matches anywhere within a name or formal alias,
+/// so that `bee` finds HONEYBEE. Terms in double quotes are matched as phrases of whole words.
+/// `NOT` binds tighter than `AND`, which binds tighter than `OR`, and terms that follow
+/// each other without an operator are combined with `AND`. Parentheses can be used for grouping.
+pub enum NameQuery {
+    Term { text: String, is_phrase: bool },
+    And(Box<NameQuery>, Box<NameQuery>),
+    Or(Box<NameQuery>, Box<NameQuery>),
+    Not(Box<NameQuery>),
+}
+
+#[derive(PartialEq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    And,
+    Or,
+    Not,
+    OpeningParenthesis,
+    ClosingParenthesis,
+}
+
+impl NameQuery {
+    /// Converts the query into an SQL condition on the code point,
+    /// looking up each term in the full-text index `NameIndex`
+    /// and pushing the values to be bound into `params`.
+    /// Terms that are not matched as whole words fall back to a substring match
+    /// on names and formal aliases. As the index only stores letters and digits,
+    /// terms with other characters such as `100%` or `a_b` are matched literally instead,
+    /// or in addition to the index if they are matched as whole words.
+    pub fn to_sql(&self, is_whole_word_match: bool, params: &mut Vec<String>) -> String {
+        match self {
+            NameQuery::Term { text, is_phrase } => {
+                let is_substring_match = !*is_phrase && !is_whole_word_match;
+                let is_literal_match = text
+                    .chars()
+                    .any(|c| !c.is_alphanumeric() && !c.is_whitespace());
+                let mut condition = None;
+
+                if !(is_substring_match && is_literal_match) {
+                    if let Some(phrase) = to_fts_phrase(text, is_substring_match) {
+                        params.push(phrase);
+                        condition = Some(String::from(
+                            "codepoint IN (SELECT rowid FROM NameIndex WHERE NameIndex MATCH ?)",
+                        ));
+                    }
+                }
+
+                if is_substring_match || is_literal_match {
+                    let pattern = to_like_pattern(text);
+                    params.push(pattern.clone());
+                    params.push(pattern);
+                    let like_condition = "(name LIKE ? ESCAPE '\\' OR codepoint IN \
+                                          (SELECT codepoint FROM NameAliases \
+                                          WHERE alias LIKE ? ESCAPE '\\'))";
+
+                    condition = Some(match condition {
+                        None => like_condition.to_string(),
+                        Some(fts_condition) if is_literal_match => {
+                            format!("({} AND {})", fts_condition, like_condition)
+                        }
+                        Some(fts_condition) => format!("({} OR {})", fts_condition, like_condition),
+                    });
+                }
+
+                condition.unwrap_or_else(|| String::from("0"))
+            }
+            NameQuery::And(left, right) => format!(
+                "({} AND {})",
+                left.to_sql(is_whole_word_match, params),
+                right.to_sql(is_whole_word_match, params)
+            ),
+            NameQuery::Or(left, right) => format!(
+                "({} OR {})",
+                left.to_sql(is_whole_word_match, params),
+                right.to_sql(is_whole_word_match, params)
+            ),
+            NameQuery::Not(query) => {
+                format!("NOT {}", query.to_sql(is_whole_word_match, params))
+            }
+        }
+    }
+
+    /// Creates a full-text query of all terms that are not negated, used to rank
+    /// the results. Exact word matches rank above prefix matches because a prefix
+    /// term is included both with and without its prefix operator.
+    pub fn to_ranking_query(&self, is_whole_word_match: bool) -> Option<String> {
+        let mut phrases = vec![];
+        self.collect_ranking_phrases(is_whole_word_match, &mut phrases);
+
+        if phrases.is_empty() {
+            None
+        } else {
+            Some(phrases.join(" OR "))
+        }
+    }
+
+    /// Returns the terms that are not negated, written like the names in the database,
+    /// so that characters whose name or formal alias equals one of them can be ranked first.
+    pub fn to_exact_names(&self) -> Vec<String> {
+        let mut names = vec![];
+        self.collect_exact_names(&mut names);
+        names
+    }
+
+    fn collect_exact_names(&self, names: &mut Vec<String>) {
+        match self {
+            NameQuery::Term { text, .. } => {
+                names.push(
+                    text.split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .to_uppercase(),
+                );
+            }
+            NameQuery::And(left, right) | NameQuery::Or(left, right) => {
+                left.collect_exact_names(names);
+                right.collect_exact_names(names);
+            }
+            NameQuery::Not(_) => {}
+        }
+    }
+
+    fn collect_ranking_phrases(&self, is_whole_word_match: bool, phrases: &mut Vec<String>) {
+        match self {
+            NameQuery::Term { text, is_phrase } => {
+                if let Some(phrase) = to_fts_phrase(text, false) {
+                    phrases.push(phrase);
+                }
+                if !*is_phrase && !is_whole_word_match {
+                    if let Some(phrase) = to_fts_phrase(text, true) {
+                        phrases.push(phrase);
+                    }
+                }
+            }
+            NameQuery::And(left, right) | NameQuery::Or(left, right) => {
+                left.collect_ranking_phrases(is_whole_word_match, phrases);
+                right.collect_ranking_phrases(is_whole_word_match, phrases);
+            }
+            NameQuery::Not(_) => {}
+        }
+    }
+}
+
+impl FromStr for NameQuery {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut tokens = tokenize(query)?.into_iter().peekable();
+
+        if tokens.peek().is_none() {
+            return Err(String::from("name query must not be empty"));
+        }
+
+        let name_query = parse_or_expression(&mut tokens)?;
+
+        match tokens.next() {
+            None => Ok(name_query),
+            Some(Token::ClosingParenthesis) => Err(String::from("unmatched ')' in name query")),
+            Some(_) => Err(String::from("unexpected operator in name query")),
+        }
+    }
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '(' => {
+                chars.next();
+                tokens.push(Token::OpeningParenthesis);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::ClosingParenthesis);
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => return Err(format!("phrase \"{} is not closed", phrase)),
+                    }
+                }
+                if phrase.trim().is_empty() {
+                    return Err(String::from("phrase in name query must not be empty"));
+                }
+                tokens.push(Token::Phrase(phrase));
+            }
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_or_expression(tokens: &mut Peekable<IntoIter<Token>>) -> Result<NameQuery, String> {
+    let mut query = parse_and_expression(tokens)?;
+
+    while tokens.peek() == Some(&Token::Or) {
+        tokens.next();
+        query = NameQuery::Or(Box::new(query), Box::new(parse_and_expression(tokens)?));
+    }
+
+    Ok(query)
+}
+
+fn parse_and_expression(tokens: &mut Peekable<IntoIter<Token>>) -> Result<NameQuery, String> {
+    let mut query = parse_not_expression(tokens)?;
+
+    loop {
+        match tokens.peek() {
+            Some(Token::And) => {
+                tokens.next();
+            }
+            Some(Token::Or) | Some(Token::ClosingParenthesis) | None => break,
+            Some(_) => {}
+        }
+        query = NameQuery::And(Box::new(query), Box::new(parse_not_expression(tokens)?));
+    }
+
+    Ok(query)
+}
+
+fn parse_not_expression(tokens: &mut Peekable<IntoIter<Token>>) -> Result<NameQuery, String> {
+    if tokens.peek() == Some(&Token::Not) {
+        tokens.next();
+        return Ok(NameQuery::Not(Box::new(parse_not_expression(tokens)?)));
+    }
+
+    match tokens.next() {
+        Some(Token::OpeningParenthesis) => {
+            let query = parse_or_expression(tokens)?;
+            match tokens.next() {
+                Some(Token::ClosingParenthesis) => Ok(query),
+                _ => Err(String::from("unmatched '(' in name query")),
+            }
+        }
+        Some(Token::Phrase(phrase)) => Ok(NameQuery::Term {
+            text: phrase,
+            is_phrase: true,
+        }),
+        Some(Token::Word(word)) => {
+            let mut words = vec![word];
+            while let Some(Token::Word(_)) = tokens.peek() {
+                if let Some(Token::Word(word)) = tokens.next() {
+                    words.push(word);
+                }
+            }
+            Ok(NameQuery::Term {
+                text: words.join(" "),
+                is_phrase: false,
+            })
+        }
+        _ => Err(String::from(
+            "operator in name query must be followed by a term",
+        )),
+    }
+}
+
+/// Converts a term into a pattern that matches its words anywhere in a name,
+/// escaping the wildcards of `LIKE`.
+fn to_like_pattern(text: &str) -> String {
+    let escaped_text = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{}%", escaped_text)
+}
+
+/// Converts a term into an FTS5 phrase of its words, whose last word is matched
+/// as a prefix if `is_prefix` is set. Returns `None` if the term contains no words,
+/// as the index only stores letters and digits.
+fn to_fts_phrase(text: &str, is_prefix: bool) -> Option<String> {
+    let words = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    let phrase = format!("\"{}\"", words.join(" "));
+
+    if is_prefix {
+        Some(format!("{} *", phrase))
+    } else {
+        Some(phrase)
+    }
+}
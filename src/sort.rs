@@ -0,0 +1,126 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::character::UnicodeCharacter;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use strum::VariantNames;
+use strum_macros::{EnumString, EnumVariantNames};
+
+#[derive(Clone, Copy, EnumString, EnumVariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum SortField {
+    Codepoint,
+    Name,
+    Block,
+    Category,
+    Age,
+}
+
+/// A sort order such as `name` or `age:desc`, ascending by default.
+pub struct SortOrder {
+    field: SortField,
+    is_descending: bool,
+}
+
+impl SortOrder {
+    /// Sorts the characters stably, so that characters with equal
+    /// sort keys keep the order in which they were found.
+    pub fn sort(&self, characters: &mut [UnicodeCharacter]) {
+        characters.sort_by(|first, second| {
+            let ordering = self.compare(first, second);
+            if self.is_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+
+    /// Returns the terms of an SQL `ORDER BY` clause that sorts like [`sort`](Self::sort),
+    /// so that the database can sort and paginate the results.
+    pub fn to_sql(&self) -> String {
+        let expressions: &[&str] = match self.field {
+            SortField::Codepoint => &["codepoint"],
+            SortField::Name => &["name"],
+            SortField::Block => &["block"],
+            SortField::Category => &["category"],
+            SortField::Age => &[
+                "CAST(substr(age, 1, instr(age, '.') - 1) AS INTEGER)",
+                "CAST(substr(age, instr(age, '.') + 1) AS INTEGER)",
+            ],
+        };
+        let direction = if self.is_descending { " DESC" } else { "" };
+
+        expressions
+            .iter()
+            .map(|expression| format!("{}{}", expression, direction))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn compare(&self, first: &UnicodeCharacter, second: &UnicodeCharacter) -> Ordering {
+        match self.field {
+            SortField::Codepoint => first.codepoint.cmp(&second.codepoint),
+            SortField::Name => first.name.cmp(&second.name),
+            SortField::Block => first.block.cmp(&second.block),
+            SortField::Category => first.category.cmp(&second.category),
+            SortField::Age => parse_age(&first.age).cmp(&parse_age(&second.age)),
+        }
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(sort_order: &str) -> Result<Self, Self::Err> {
+        let mut parts = sort_order.splitn(2, ':');
+        let field_name = parts.next().unwrap_or_default().trim();
+        let field = SortField::from_str(&field_name.to_lowercase()).map_err(|_| {
+            format!(
+                "unknown sort field '{}', available fields are {}",
+                field_name,
+                SortField::VARIANTS.join(", ")
+            )
+        })?;
+        let is_descending = match parts
+            .next()
+            .map(|direction| direction.trim().to_lowercase())
+        {
+            None => false,
+            Some(direction) if direction == "asc" => false,
+            Some(direction) if direction == "desc" => true,
+            Some(direction) => {
+                return Err(format!(
+                    "unknown sort direction '{}', expected asc or desc",
+                    direction
+                ))
+            }
+        };
+
+        Ok(SortOrder {
+            field,
+            is_descending,
+        })
+    }
+}
+
+fn parse_age(age: &str) -> (u32, u32) {
+    let mut parts = age
+        .splitn(2, '.')
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0))
+}
@@ -0,0 +1,312 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::output::{format_aliases, format_decomposition, CaseMappingRecord, CharacterRecord};
+use std::str::FromStr;
+use strum::VariantNames;
+use strum_macros::{EnumString, EnumVariantNames};
+use unicode_width::UnicodeWidthStr;
+
+/// A user-defined output format such as `{char}\t{codepoint:04x}\t{name:lower}`.
+///
+/// Fields are written in braces and may carry a format specification after a colon.
+/// Numeric fields accept an optional zero flag, a width and one of the radixes
+/// `d`, `x`, `X`, `o` or `b`. All fields accept a width as well as `lower` or `upper`.
+/// The escape sequences `\t`, `\n`, `\r`, `\\`, `\{` and `\}` are supported.
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+enum Segment {
+    Literal(String),
+    Field(Field, FieldFormat),
+}
+
+#[derive(EnumString, EnumVariantNames, Clone, Copy)]
+#[strum(serialize_all = "snake_case")]
+enum Field {
+    Char,
+    Codepoint,
+    Hex,
+    Name,
+    Category,
+    CategoryDescription,
+    Block,
+    Age,
+    Aliases,
+    #[strum(serialize = "unicode_1_name")]
+    Unicode1Name,
+    IsoComment,
+    CombiningClass,
+    Decomposition,
+    Uppercase,
+    Lowercase,
+    Titlecase,
+    CaseFolding,
+    BidiClass,
+    BidiMirrored,
+    BidiMirroringGlyph,
+    BidiPairedBracket,
+    BidiPairedBracketType,
+    NumericType,
+    NumericValue,
+    DecompositionGroup,
+    GraphemeCluster,
+    Score,
+}
+
+impl Field {
+    fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Field::Codepoint | Field::CombiningClass | Field::GraphemeCluster | Field::Score
+        )
+    }
+}
+
+#[derive(Default)]
+struct FieldFormat {
+    is_zero_padded: bool,
+    width: usize,
+    radix: Option<char>,
+    letter_case: Option<LetterCase>,
+}
+
+enum LetterCase {
+    Lower,
+    Upper,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let escaped_char = match chars.next() {
+                        Some('t') => '\t',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some(c @ '\\') | Some(c @ '{') | Some(c @ '}') => c,
+                        Some(c) => return Err(format!("unknown escape sequence '\\{}'", c)),
+                        None => return Err(String::from("template must not end with '\\'")),
+                    };
+                    literal.push(escaped_char);
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(format!("field '{{{}' is not closed", placeholder)),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(literal.clone()));
+                        literal.clear();
+                    }
+                    segments.push(parse_placeholder(&placeholder)?);
+                }
+                '}' => {
+                    return Err(String::from(
+                        "unmatched '}', write '\\}' for a literal brace",
+                    ))
+                }
+                _ => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, record: &CharacterRecord) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Field(field, field_format) => render_field(*field, field_format, record),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        Template::parse(template)
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Segment, String> {
+    let mut parts = placeholder.splitn(2, ':');
+    let field_name = parts.next().unwrap().trim();
+    let field = Field::from_str(field_name).map_err(|_| {
+        format!(
+            "unknown field '{}', available fields are: {}",
+            field_name,
+            Field::VARIANTS.join(", ")
+        )
+    })?;
+    let field_format = match parts.next() {
+        Some(spec) => parse_field_format(field, field_name, spec.trim())?,
+        None => FieldFormat::default(),
+    };
+
+    Ok(Segment::Field(field, field_format))
+}
+
+fn parse_field_format(field: Field, field_name: &str, spec: &str) -> Result<FieldFormat, String> {
+    let mut field_format = FieldFormat::default();
+
+    match spec {
+        "lower" => field_format.letter_case = Some(LetterCase::Lower),
+        "upper" => field_format.letter_case = Some(LetterCase::Upper),
+        _ => {
+            let mut digits = spec;
+
+            if let Some(radix) = spec.chars().last().filter(|c| "dxXob".contains(*c)) {
+                field_format.radix = Some(radix);
+                digits = &spec[..spec.len() - 1];
+            }
+            if digits.starts_with('0') && digits.len() > 1 {
+                field_format.is_zero_padded = true;
+                digits = &digits[1..];
+            }
+            if !digits.is_empty() {
+                field_format.width = digits
+                    .parse()
+                    .map_err(|_| format!("invalid format '{}' for field '{}'", spec, field_name))?;
+            }
+            if (field_format.radix.is_some() || field_format.is_zero_padded) && !field.is_numeric()
+            {
+                return Err(format!(
+                    "format '{}' can only be applied to the numeric fields codepoint, \
+                     combining_class, grapheme_cluster and score, not to '{}'",
+                    spec, field_name
+                ));
+            }
+        }
+    }
+
+    Ok(field_format)
+}
+
+fn render_field(field: Field, field_format: &FieldFormat, record: &CharacterRecord) -> String {
+    let value = match field {
+        Field::Char => record.glyph.clone(),
+        Field::Codepoint => format_number(record.codepoint as usize, field_format),
+        Field::Hex => record.hex.clone(),
+        Field::Name => record.name.to_string(),
+        Field::Category => record.category.to_string(),
+        Field::CategoryDescription => record.category_description.to_string(),
+        Field::Block => record.block.to_string(),
+        Field::Age => record.age.to_string(),
+        Field::Aliases => format_aliases(&record.aliases),
+        Field::Unicode1Name => record.unicode_1_name.unwrap_or_default().to_string(),
+        Field::IsoComment => record.iso_comment.unwrap_or_default().to_string(),
+        Field::CombiningClass => format_number(record.combining_class as usize, field_format),
+        Field::Decomposition => record
+            .decomposition
+            .as_ref()
+            .map(format_decomposition)
+            .unwrap_or_default(),
+        Field::Uppercase => format_case_mapping(&record.uppercase, record),
+        Field::Lowercase => format_case_mapping(&record.lowercase, record),
+        Field::Titlecase => format_case_mapping(&record.titlecase, record),
+        Field::CaseFolding => format_case_mapping(&record.case_folding, record),
+        Field::BidiClass => record.bidi_class.to_string(),
+        Field::BidiMirrored => String::from(if record.bidi_mirrored { "Y" } else { "N" }),
+        Field::BidiMirroringGlyph => record
+            .bidi_mirroring_glyph
+            .as_ref()
+            .map(|mirroring_glyph| mirroring_glyph.glyph.clone())
+            .unwrap_or_default(),
+        Field::BidiPairedBracket => record
+            .bidi_paired_bracket
+            .as_ref()
+            .map(|paired_bracket| paired_bracket.glyph.clone())
+            .unwrap_or_default(),
+        Field::BidiPairedBracketType => record
+            .bidi_paired_bracket
+            .as_ref()
+            .map(|paired_bracket| paired_bracket.bracket_type.to_string())
+            .unwrap_or_default(),
+        Field::NumericType => record.numeric_type.unwrap_or_default().to_string(),
+        Field::NumericValue => record.numeric_value.unwrap_or_default().to_string(),
+        Field::DecompositionGroup => record.decomposition_group.unwrap_or_default().to_string(),
+        Field::GraphemeCluster => record
+            .grapheme_cluster
+            .map(|grapheme_cluster| format_number(grapheme_cluster, field_format))
+            .unwrap_or_default(),
+        Field::Score => record
+            .score
+            .map(|score| format_number(score as usize, field_format))
+            .unwrap_or_default(),
+    };
+    let value = match field_format.letter_case {
+        Some(LetterCase::Lower) => value.to_lowercase(),
+        Some(LetterCase::Upper) => value.to_uppercase(),
+        None => value,
+    };
+    let value_width = value.width();
+
+    if field_format.width > value_width {
+        let padding = " ".repeat(field_format.width - value_width);
+        if field.is_numeric() {
+            format!("{}{}", padding, value)
+        } else {
+            format!("{}{}", value, padding)
+        }
+    } else {
+        value
+    }
+}
+
+/// Returns the full case mapping, which is the character itself if it is not mapped.
+fn format_case_mapping(
+    case_mapping: &Option<CaseMappingRecord>,
+    record: &CharacterRecord,
+) -> String {
+    case_mapping
+        .as_ref()
+        .map(|case_mapping| case_mapping.full.clone())
+        .unwrap_or_else(|| record.glyph.clone())
+}
+
+fn format_number(number: usize, field_format: &FieldFormat) -> String {
+    let width = if field_format.is_zero_padded {
+        field_format.width
+    } else {
+        0
+    };
+
+    match field_format.radix {
+        Some('x') => format!("{:0width$x}", number, width = width),
+        Some('X') => format!("{:0width$X}", number, width = width),
+        Some('o') => format!("{:0width$o}", number, width = width),
+        Some('b') => format!("{:0width$b}", number, width = width),
+        _ => format!("{:0width$}", number, width = width),
+    }
+}
@@ -0,0 +1,551 @@
+/*
+ * Copyright © 2021 Peter M. Stahl pemistahl@gmail.com
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either expressed or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::filter::{AgeFilter, BidiClassFilter, BlockFilter, CategoryFilter};
+use regex_syntax::hir::{Class, HirKind};
+use regex_syntax::ParserBuilder;
+use rusqlite::{Connection, OptionalExtension};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+const MAX_CODEPOINT: u32 = 0x10FFFF;
+
+/// A set expression in the syntax of ICU's UnicodeSet, such as
+/// `[\p{Lu}&&\p{Block=Greek_and_Coptic}]`, `[\p{L}-\p{Latin}]` or `[a-zà-ÿ]`.
+///
+/// Sets contain literal characters, ranges of them, properties and nested sets,
+/// which are joined by union. Characters given by name or alias, such as
+/// `\N{LATIN SMALL LETTER A}`, are looked up in the database on evaluation. The operators `&&` or `&` intersect and `--` or `-`
+/// subtract the following operand, evaluated from left to right. General category,
+/// bidi class, block and age are looked up in the database, where `\p{Age=6.0}`
+/// includes all characters added up to Unicode 6.0 and `\p{Cn}` all code points
+/// that are not assigned. All other properties such as scripts are taken from the
+/// Unicode tables of the regex engine, restricted to the characters assigned in the
+/// database, so that the values of these characters follow the engine's Unicode version.
+pub enum UnicodeSet {
+    Range(Literal, Literal),
+    Property {
+        property: Property,
+        is_negated: bool,
+    },
+    Set {
+        operands: Vec<(Operator, UnicodeSet)>,
+        is_complemented: bool,
+    },
+}
+
+pub enum Property {
+    Category(CategoryFilter),
+    BidiClass(BidiClassFilter),
+    Block(BlockFilter),
+    Age(AgeFilter),
+    Regex(Vec<(u32, u32)>),
+}
+
+#[derive(Clone)]
+pub enum Literal {
+    Codepoint(u32),
+    Name(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Operator {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl UnicodeSet {
+    /// Evaluates the set to a sorted list of disjoint code point ranges.
+    pub fn evaluate(&self, database: &Connection) -> Result<Vec<(u32, u32)>, String> {
+        match self {
+            UnicodeSet::Range(start, end) => {
+                let (start, end) = (start.resolve(database)?, end.resolve(database)?);
+                check_range_order(start, end)?;
+                Ok(vec![(start, end)])
+            }
+            UnicodeSet::Property {
+                property,
+                is_negated,
+            } => {
+                let ranges = property.evaluate(database)?;
+                Ok(if *is_negated {
+                    complement(&ranges)
+                } else {
+                    ranges
+                })
+            }
+            UnicodeSet::Set {
+                operands,
+                is_complemented,
+            } => {
+                let mut ranges = vec![];
+
+                for (operator, operand) in operands {
+                    let operand_ranges = operand.evaluate(database)?;
+                    ranges = match operator {
+                        Operator::Union => {
+                            ranges.extend(operand_ranges);
+                            normalize_ranges(ranges)
+                        }
+                        Operator::Intersection => intersect(&ranges, &operand_ranges),
+                        Operator::Difference => intersect(&ranges, &complement(&operand_ranges)),
+                    };
+                }
+
+                Ok(if *is_complemented {
+                    complement(&ranges)
+                } else {
+                    ranges
+                })
+            }
+        }
+    }
+}
+
+impl Property {
+    fn evaluate(&self, database: &Connection) -> Result<Vec<(u32, u32)>, String> {
+        let mut params = vec![];
+        let condition = match self {
+            Property::Category(category_filter) => {
+                let condition = category_filter.to_sql(&mut params);
+
+                // Unassigned code points are not stored, so they are derived as the
+                // complement of all code points in the database.
+                if category_filter.includes_unassigned() {
+                    let mut ranges = retrieve_ranges(database, &condition, &params);
+                    ranges.extend(complement(&retrieve_ranges(database, "1", &[])));
+                    return Ok(normalize_ranges(ranges));
+                }
+
+                condition
+            }
+            Property::BidiClass(bidi_class_filter) => bidi_class_filter.to_sql(&mut params),
+            Property::Block(block_filter) => {
+                block_filter.validate(database)?;
+                block_filter.to_sql(&mut params)
+            }
+            Property::Age(age_filter) => age_filter.to_sql(),
+            Property::Regex(ranges) => {
+                return Ok(intersect(ranges, &retrieve_ranges(database, "1", &[])))
+            }
+        };
+
+        Ok(retrieve_ranges(database, &condition, &params))
+    }
+}
+
+impl Literal {
+    fn resolve(&self, database: &Connection) -> Result<u32, String> {
+        match self {
+            Literal::Codepoint(codepoint) => Ok(*codepoint),
+            Literal::Name(name) => database
+                .query_row(
+                    "SELECT codepoint FROM UnicodeData WHERE name = ?1 \
+                     UNION ALL SELECT codepoint FROM NameAliases WHERE alias = ?1 \
+                     LIMIT 1",
+                    [name],
+                    |row| row.get(0),
+                )
+                .optional()
+                .expect("Character name could not be looked up in database")
+                .ok_or_else(|| format!("there is no character named '{}'", name)),
+        }
+    }
+}
+
+fn retrieve_ranges(database: &Connection, condition: &str, params: &[String]) -> Vec<(u32, u32)> {
+    let sql = format!(
+        "SELECT codepoint FROM UnicodeData WHERE {} ORDER BY codepoint",
+        condition
+    );
+    let mut statement = database
+        .prepare(&sql)
+        .expect("SQL statement could not be prepared");
+    let codepoints = statement
+        .query_map(params, |row| row.get::<_, u32>(0))
+        .expect("SQL query failed")
+        .map(|codepoint| codepoint.expect("Code point could not be retrieved"));
+
+    let mut ranges: Vec<(u32, u32)> = vec![];
+
+    for codepoint in codepoints {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == codepoint => *end = codepoint,
+            _ => ranges.push((codepoint, codepoint)),
+        }
+    }
+
+    ranges
+}
+
+impl FromStr for UnicodeSet {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let mut chars = expression.trim().chars().peekable();
+
+        let unicode_set = match chars.peek() {
+            Some('[') => parse_set(&mut chars)?,
+            Some('\\') => {
+                chars.next();
+                match chars.next() {
+                    Some(c @ 'p') | Some(c @ 'P') => parse_property(&mut chars, c == 'P')?,
+                    _ => return Err(String::from("set must start with '[' or \\p")),
+                }
+            }
+            _ => return Err(String::from("set must start with '[' or \\p")),
+        };
+
+        match chars.next() {
+            None => Ok(unicode_set),
+            Some(c) => Err(format!("unexpected '{}' after the end of the set", c)),
+        }
+    }
+}
+
+fn parse_set(chars: &mut Peekable<Chars>) -> Result<UnicodeSet, String> {
+    chars.next();
+
+    if chars.peek() == Some(&':') {
+        chars.next();
+        let is_negated = chars.peek() == Some(&'^');
+        if is_negated {
+            chars.next();
+        }
+        let name = read_until(chars, ':')?;
+        if chars.next() != Some(']') {
+            return Err(format!("property [:{}: is not closed", name));
+        }
+        return Ok(UnicodeSet::Property {
+            property: resolve_property(&name)?,
+            is_negated,
+        });
+    }
+
+    let is_complemented = chars.peek() == Some(&'^');
+    if is_complemented {
+        chars.next();
+    }
+
+    let mut operands = vec![];
+
+    loop {
+        skip_whitespace(chars);
+
+        let operator = match chars.peek() {
+            None => return Err(String::from("set is not closed with ']'")),
+            Some(']') => {
+                chars.next();
+                break;
+            }
+            Some('&') => {
+                chars.next();
+                if chars.peek() == Some(&'&') {
+                    chars.next();
+                }
+                Operator::Intersection
+            }
+            Some('-') if !operands.is_empty() => {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+                if lookahead.peek() == Some(&'-') {
+                    lookahead.next();
+                }
+                skip_whitespace(&mut lookahead);
+                if is_set_operand(&mut lookahead) {
+                    *chars = lookahead;
+                    Operator::Difference
+                } else {
+                    Operator::Union
+                }
+            }
+            Some(_) => Operator::Union,
+        };
+
+        if operator != Operator::Union {
+            skip_whitespace(chars);
+            if operands.is_empty() {
+                return Err(String::from("set operator must follow an operand"));
+            }
+            if !is_set_operand(chars) {
+                return Err(String::from(
+                    "set operator must be followed by a set or property",
+                ));
+            }
+        }
+
+        let operand = match chars.peek() {
+            Some('[') => parse_set(chars)?,
+            Some('\\') => {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+                match lookahead.peek() {
+                    Some(&c) if c == 'p' || c == 'P' => {
+                        chars.next();
+                        chars.next();
+                        parse_property(chars, c == 'P')?
+                    }
+                    _ => parse_literal_range(chars)?,
+                }
+            }
+            _ => parse_literal_range(chars)?,
+        };
+
+        operands.push((operator, operand));
+    }
+
+    Ok(UnicodeSet::Set {
+        operands,
+        is_complemented,
+    })
+}
+
+fn parse_property(chars: &mut Peekable<Chars>, is_negated: bool) -> Result<UnicodeSet, String> {
+    if chars.next() != Some('{') {
+        return Err(String::from("property must be written as \\p{...}"));
+    }
+
+    let name = read_until(chars, '}')?;
+
+    Ok(UnicodeSet::Property {
+        property: resolve_property(&name)?,
+        is_negated,
+    })
+}
+
+fn parse_literal_range(chars: &mut Peekable<Chars>) -> Result<UnicodeSet, String> {
+    let start = parse_literal(chars)?;
+    let mut lookahead = chars.clone();
+
+    skip_whitespace(&mut lookahead);
+
+    if lookahead.next() == Some('-') {
+        skip_whitespace(&mut lookahead);
+        if !is_set_operand(&mut lookahead) && lookahead.peek() != Some(&']') {
+            *chars = lookahead;
+            let end = parse_literal(chars)?;
+            if let (Literal::Codepoint(start), Literal::Codepoint(end)) = (&start, &end) {
+                check_range_order(*start, *end)?;
+            }
+            return Ok(UnicodeSet::Range(start, end));
+        }
+    }
+
+    Ok(UnicodeSet::Range(start.clone(), start))
+}
+
+fn parse_literal(chars: &mut Peekable<Chars>) -> Result<Literal, String> {
+    let codepoint = match chars.next() {
+        Some('\\') => match chars.next() {
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                parse_hex(&read_until(chars, '}')?)?
+            }
+            Some('x') if chars.peek() == Some(&'{') => {
+                chars.next();
+                parse_hex(&read_until(chars, '}')?)?
+            }
+            Some('N') => {
+                if chars.next() != Some('{') {
+                    return Err(String::from("character name must be written as \\N{...}"));
+                }
+                return parse_name(&read_until(chars, '}')?);
+            }
+            Some('u') => parse_hex(&read_count(chars, 4))?,
+            Some('U') => parse_hex(&read_count(chars, 8))?,
+            Some('x') => parse_hex(&read_count(chars, 2))?,
+            Some('t') => '\t' as u32,
+            Some('n') => '\n' as u32,
+            Some('r') => '\r' as u32,
+            Some(c) => c as u32,
+            None => return Err(String::from("escape sequence must not be empty")),
+        },
+        Some(c) => c as u32,
+        None => return Err(String::from("set is not closed with ']'")),
+    };
+
+    Ok(Literal::Codepoint(codepoint))
+}
+
+fn parse_name(name: &str) -> Result<Literal, String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if name.is_empty() {
+        return Err(String::from("character name must not be empty"));
+    }
+
+    Ok(Literal::Name(name.to_uppercase()))
+}
+
+fn check_range_order(start: u32, end: u32) -> Result<(), String> {
+    if start > end {
+        return Err(format!(
+            "range U+{:04X}-U+{:04X} must not start after its end",
+            start, end
+        ));
+    }
+    Ok(())
+}
+
+fn resolve_property(expression: &str) -> Result<Property, String> {
+    let (name, value) = match expression.find('=') {
+        Some(index) => (
+            Some(normalize_property_name(&expression[..index])),
+            expression[index + 1..].trim(),
+        ),
+        None => (None, expression.trim()),
+    };
+
+    match name.as_deref() {
+        Some("gc") | Some("generalcategory") => {
+            return value.parse::<CategoryFilter>().map(Property::Category)
+        }
+        Some("bc") | Some("bidiclass") => {
+            return value.parse::<BidiClassFilter>().map(Property::BidiClass)
+        }
+        Some("blk") | Some("block") => return value.parse::<BlockFilter>().map(Property::Block),
+        Some("age") => {
+            return format!("..{}", value)
+                .parse::<AgeFilter>()
+                .map(Property::Age)
+        }
+        Some(_) => {}
+        None => {
+            if let Ok(category_filter) = value.parse::<CategoryFilter>() {
+                return Ok(Property::Category(category_filter));
+            }
+        }
+    }
+
+    let hir = ParserBuilder::new()
+        .build()
+        .parse(&format!("\\p{{{}}}", expression))
+        .map_err(|_| format!("unknown property '{}'", expression))?;
+
+    match hir.kind() {
+        HirKind::Class(Class::Unicode(class)) => Ok(Property::Regex(
+            class
+                .iter()
+                .map(|range| (range.start() as u32, range.end() as u32))
+                .collect(),
+        )),
+        _ => Err(format!("unknown property '{}'", expression)),
+    }
+}
+
+fn is_set_operand(chars: &mut Peekable<Chars>) -> bool {
+    let mut lookahead = chars.clone();
+    match lookahead.next() {
+        Some('[') => true,
+        Some('\\') => matches!(lookahead.next(), Some('p') | Some('P')),
+        _ => false,
+    }
+}
+
+fn read_until(chars: &mut Peekable<Chars>, delimiter: char) -> Result<String, String> {
+    let mut text = String::new();
+
+    loop {
+        match chars.next() {
+            Some(c) if c == delimiter => return Ok(text),
+            Some(c) => text.push(c),
+            None => return Err(format!("expected '{}' after '{}'", delimiter, text)),
+        }
+    }
+}
+
+fn read_count(chars: &mut Peekable<Chars>, count: usize) -> String {
+    chars.take(count).collect()
+}
+
+fn parse_hex(hex: &str) -> Result<u32, String> {
+    match u32::from_str_radix(hex.trim(), 16) {
+        Ok(codepoint) if codepoint <= MAX_CODEPOINT => Ok(codepoint),
+        Ok(_) => Err(format!(
+            "'{}' is beyond the last Unicode code point U+10FFFF",
+            hex
+        )),
+        Err(_) => Err(format!("'{}' is not a hexadecimal code point", hex)),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn normalize_property_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn normalize_ranges(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort_unstable();
+
+    let mut normalized_ranges: Vec<(u32, u32)> = vec![];
+
+    for (start, end) in ranges {
+        match normalized_ranges.last_mut() {
+            Some((_, last_end)) if start <= last_end.saturating_add(1) => {
+                *last_end = (*last_end).max(end)
+            }
+            _ => normalized_ranges.push((start, end)),
+        }
+    }
+
+    normalized_ranges
+}
+
+fn intersect(first: &[(u32, u32)], second: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut ranges = vec![];
+    let (mut i, mut j) = (0, 0);
+
+    while i < first.len() && j < second.len() {
+        let start = first[i].0.max(second[j].0);
+        let end = first[i].1.min(second[j].1);
+
+        if start <= end {
+            ranges.push((start, end));
+        }
+        if first[i].1 < second[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    ranges
+}
+
+fn complement(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut complemented_ranges = vec![];
+    let mut next_start = 0;
+
+    for &(start, end) in ranges {
+        if start > next_start {
+            complemented_ranges.push((next_start, start - 1));
+        }
+        next_start = end + 1;
+    }
+
+    if next_start <= MAX_CODEPOINT {
+        complemented_ranges.push((next_start, MAX_CODEPOINT));
+    }
+
+    complemented_ranges
+}
@@ -17,12 +17,12 @@
 use assert_cmd::prelude::*;
 use indoc::indoc;
 use predicates::prelude::*;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 #[test]
 fn succeeds_with_character_search_option() {
     let mut chr = init_command();
-    chr.args(&["--no-paging", "Ä", "@", "$", "ß", "!"]);
+    chr.args(["--no-paging", "Ä", "@", "$", "ß", "!"]);
     chr.assert()
         .success()
         .stdout(predicate::str::contains(indoc!(
@@ -44,6 +44,7 @@ fn succeeds_with_character_search_option() {
 
             4.	Ä	U+00C4
             LATIN CAPITAL LETTER A WITH DIAERESIS
+            Unicode 1.0 name LATIN CAPITAL LETTER A DIAERESIS
             Latin-1 Supplement	Uppercase Letter
             since 1.1
 
@@ -58,7 +59,7 @@ fn succeeds_with_character_search_option() {
 #[test]
 fn succeeds_with_name_search_option() {
     let mut chr = init_command();
-    chr.args(&["--no-paging", "--name", "honey"]);
+    chr.args(["--no-paging", "--name", "honey"]);
     chr.assert()
         .success()
         .stdout(predicate::str::contains(indoc!(
@@ -76,24 +77,1466 @@ fn succeeds_with_name_search_option() {
         )));
 }
 
+#[test]
+fn succeeds_with_boolean_name_search() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{name}",
+        "--name",
+        "latin small letter a AND acute NOT (dot OR ring OR macron)",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        LATIN SMALL LETTER A WITH ACUTE
+        LATIN SMALL LETTER A WITH CIRCUMFLEX AND ACUTE
+        LATIN SMALL LETTER A WITH BREVE AND ACUTE
+        LATIN SMALL LETTER AE WITH ACUTE
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_ranked_name_and_alias_search() {
+    let mut chr = init_command();
+    chr.args(["--template", "{hex} {name}", "--name", "null OR zwj"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+0000 <control-0000>
+        U+200D ZERO WIDTH JOINER
+        U+2400 SYMBOL FOR NULL
+        U+1D159 MUSICAL SYMBOL NULL NOTEHEAD
+        U+16B67 PAHAWH HMONG SIGN ZWJ THAJ
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_aliases_and_legacy_names() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "U+000A"]);
+    chr.assert().success().stdout(predicate::str::contains(indoc!(
+        "
+        <control-000A>
+        also known as LINE FEED (control), NEW LINE (control), END OF LINE (control), LF (abbreviation), NL (abbreviation), EOL (abbreviation)
+        Unicode 1.0 name LINE FEED (LF)
+        Basic Latin	Control Character
+        "
+    )));
+
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{hex} {name}",
+        "--whole-words",
+        "--name",
+        "nbsp OR bom OR \"non-breaking space\"",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+00A0 NO-BREAK SPACE
+        U+FEFF ZERO WIDTH NO-BREAK SPACE
+        U+BD04 HANGUL SYLLABLE BOM
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_decomposition_tree_in_detailed_view() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--details", "U+01D5", "U+0301"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        1.	Ǖ	U+01D5
+        LATIN CAPITAL LETTER U WITH DIAERESIS AND MACRON
+        Unicode 1.0 name LATIN CAPITAL LETTER U DIAERESIS MACRON
+        combining class 0 (Not Reordered)
+        canonical decomposition
+        ├─ U+00DC Ü LATIN CAPITAL LETTER U WITH DIAERESIS
+        │  ├─ U+0055 U LATIN CAPITAL LETTER U
+        │  └─ U+0308 ̈ COMBINING DIAERESIS
+        └─ U+0304 ̄ COMBINING MACRON
+        lowercase → ǖ U+01D6
+        case folding → ǖ U+01D6
+        bidi class L Left To Right (strong)
+        Latin Extended-B	Uppercase Letter
+        since 1.1
+
+        2.	́	U+0301
+        COMBINING ACUTE ACCENT
+        Unicode 1.0 name NON-SPACING ACUTE
+        combining class 230 (Above)
+        bidi class NSM Nonspacing Mark (weak)
+        Combining Diacritical Marks	Non-spacing Mark
+        since 1.1
+        "
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--template", "{hex} {decomposition}", "ﬁ", "각"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+AC01 U+AC00 U+11A8
+        U+FB01 <compat> U+0066 U+0069
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_variants_grouped_by_decomposition() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--variants", "ﬁ"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        --- compatibility decomposition <compat> ---
+
+        1.	ﬃ	U+FB03
+        LATIN SMALL LIGATURE FFI
+        Alphabetic Presentation Forms	Lowercase Letter
+        since 1.1
+        "
+    ));
+
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{decomposition_group} {char} {hex}",
+        "--variants",
+        "s",
+        "--block",
+        "Latin Extended Additional,Halfwidth and Fullwidth Forms",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        canonical ṡ U+1E61
+        canonical ṣ U+1E63
+        canonical ṥ U+1E65
+        canonical ṧ U+1E67
+        canonical ṩ U+1E69
+        wide ｓ U+FF53
+        compat ẛ U+1E9B
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_annotations_in_detailed_view() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--details", "U+00A0"]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains(indoc!(
+            "
+        Unicode 1.0 name NON-BREAKING SPACE
+        • commonly abbreviated as NBSP
+        → U+0020 SPACE
+        → U+2007 FIGURE SPACE
+        → U+202F NARROW NO-BREAK SPACE
+        → U+2060 WORD JOINER
+        → U+FEFF ZERO WIDTH NO-BREAK SPACE
+        ≈ <noBreak> U+0020
+        combining class 0 (Not Reordered)
+        compatibility decomposition <noBreak>
+        └─ U+0020   SPACE
+        bidi class CS Common Separator (weak)
+        Latin-1 Supplement	Space Separator
+        "
+        )));
+
+    let mut chr = init_command();
+    chr.args(["--format", "ndjson", "--details", "U+2212"]);
+    chr.assert().success().stdout(predicate::str::contains(
+        r#""case_folding":null,"bidi_class":"ES","bidi_class_description":"European Separator","bidi_class_type":"weak","bidi_mirrored":false,"bidi_mirroring_glyph":null,"bidi_paired_bracket":null,"numeric_type":null,"numeric_value":null,"annotations":[{"text":"HYPHEN-MINUS","type":"cross_reference","reference":"U+002D"},"#,
+    ));
+
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{hex} {name}",
+        "--whole-words",
+        "--name",
+        "factorial",
+    ]);
+    chr.assert().success().stdout("U+0021 EXCLAMATION MARK\n");
+}
+
+#[test]
+fn succeeds_with_whole_word_and_phrase_name_search() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{name}",
+        "--whole-words",
+        "--name",
+        "\"honey pot\" OR bee",
+    ]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains("HONEY POT\n"))
+        .stdout(predicate::str::contains("DESERET SMALL LETTER BEE\n"))
+        .stdout(predicate::str::contains("HONEYBEE").not())
+        .stdout(predicate::str::contains("BEETLE").not());
+}
+
+#[test]
+fn succeeds_with_substring_name_search() {
+    let mut chr = init_command();
+    chr.args(["--template", "{name}", "--name", "bee"]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::starts_with("ETHIOPIC SYLLABLE BEE\n"))
+        .stdout(predicate::str::contains("BEETLE\n"))
+        .stdout(predicate::str::contains("HONEYBEE\n"));
+}
+
+#[test]
+fn succeeds_with_exact_name_match_ranked_first() {
+    let mut chr = init_command();
+    chr.args(["--template", "{hex} {name}", "--name", "line feed"]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::starts_with("U+000A <control-000A>\n"));
+}
+
+#[test]
+fn succeeds_with_quotes_and_wildcards_in_name_search() {
+    let mut chr = init_command();
+    chr.args(["--template", "{name}", "--name", "it's 100%_"]);
+    chr.assert().success().stdout("");
+}
+
+#[test]
+fn succeeds_with_punctuation_matched_literally_in_name_search() {
+    for query in ["100%", "a_b"] {
+        let mut chr = init_command();
+        chr.args(["--template", "{name}", "--name", query]);
+        chr.assert().success().stdout("");
+    }
+
+    let mut chr = init_command();
+    chr.args(["--template", "{name}", "--name", "selector-100"]);
+    chr.assert().success().stdout("VARIATION SELECTOR-100\n");
+}
+
+#[test]
+fn succeeds_with_regex_name_search() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{hex} {name}",
+        "--name-regex",
+        "^CJK COMPATIBILITY IDEOGRAPH-F90[0-2]$",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+F900 CJK COMPATIBILITY IDEOGRAPH-F900
+        U+F901 CJK COMPATIBILITY IDEOGRAPH-F901
+        U+F902 CJK COMPATIBILITY IDEOGRAPH-F902
+        "
+    ));
+}
+
+#[test]
+fn fails_with_invalid_name_regex() {
+    let mut chr = init_command();
+    chr.args(["--name-regex", "(acute"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--name-regex <REGEX>': regex parse error",
+    ));
+}
+
+#[test]
+fn succeeds_with_derived_names_and_labels() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{hex} {name}",
+        "U+0007",
+        "U+4E00",
+        "U+AC01",
+        "U+D800",
+        "U+E000",
+        "U+17000",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+0007 <control-0007>
+        U+4E00 CJK UNIFIED IDEOGRAPH-4E00
+        U+AC01 HANGUL SYLLABLE GAG
+        U+D800 <surrogate-D800>
+        U+E000 <private-use-E000>
+        U+17000 TANGUT IDEOGRAPH-17000
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_name_search_for_derived_names() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char} {name}",
+        "--whole-words",
+        "--name",
+        "\"hangul syllable gag\" OR \"cjk unified ideograph 4e00\"",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        각 HANGUL SYLLABLE GAG
+        一 CJK UNIFIED IDEOGRAPH-4E00
+        🈩 SQUARED CJK UNIFIED IDEOGRAPH-4E00
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_fuzzy_name_search() {
+    let mut chr = init_command();
+    chr.args(["--template", "{score} {char} {name}", "--fuzzy", "honey"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        99 🍯 HONEY POT
+        80 🐝 HONEYBEE
+        59 💰 MONEY BAG
+        58 💸 MONEY WITH WINGS
+        58 🤑 MONEY-MOUTH FACE
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_fuzzy_alias_search() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{score} U+{codepoint:04X} {name}",
+        "--fuzzy",
+        "byte ordre mark",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        86 U+FEFF ZERO WIDTH NO-BREAK SPACE
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_category_block_and_age_filters() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char} {category} {age}",
+        "--category",
+        "P,Sc",
+        "--block",
+        "latin-1 supplement",
+        "--age",
+        "<=1.1",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        ¡ Po 1.1
+        ¢ Sc 1.1
+        £ Sc 1.1
+        ¤ Sc 1.1
+        ¥ Sc 1.1
+        § Po 1.1
+        « Pi 1.1
+        ¶ Po 1.1
+        · Po 1.1
+        » Pf 1.1
+        ¿ Po 1.1
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_bidi_properties_in_detailed_view() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--details", "("]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains(indoc!(
+            "
+            bidi class ON Other Neutral (neutral)
+            mirrored → ) U+0029
+            opening bracket paired with ) U+0029
+            "
+        )));
+
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char} {bidi_class} {bidi_mirrored} {bidi_mirroring_glyph}",
+        "«",
+        "∛",
+    ]);
+    chr.assert().success().stdout("« ON Y »\n∛ ON Y \n");
+}
+
+#[test]
+fn succeeds_with_numeric_values_in_detailed_view() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--details", "½"]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains(indoc!(
+            "
+            bidi class ON Other Neutral (neutral)
+            numeric value 1/2 = 0.5 (numeric)
+            Latin-1 Supplement	Other Number
+            "
+        )));
+
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char} {numeric_type} {numeric_value}",
+        "٣",
+        "²",
+        "三",
+        "A",
+    ]);
+    chr.assert()
+        .success()
+        .stdout("A  \n² Digit 2\n٣ Decimal 3\n三 Numeric 3\n");
+}
+
+#[test]
+fn succeeds_with_digit_parsing_of_string() {
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "١٢٣"]);
+    chr.assert().success().stdout("123\n");
+
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "०४२"]);
+    chr.assert().success().stdout("42\n");
+
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "１２"]);
+    chr.assert().success().stdout("12\n");
+}
+
+#[test]
+fn succeeds_with_rational_numeric_values_in_detailed_view() {
+    for (character, numeric_value) in [
+        ("U+0F33", "numeric value -1/2 = -0.5 (numeric)"),
+        ("U+0D76", "numeric value 1/16 = 0.0625 (numeric)"),
+        ("U+5146", "numeric value 1000000000000 (numeric)"),
+    ] {
+        let mut chr = init_command();
+        chr.args(["--no-paging", "--details", character]);
+        chr.assert()
+            .success()
+            .stdout(predicate::str::contains(numeric_value));
+    }
+}
+
+#[test]
+fn succeeds_with_digit_parsing_of_leading_zeros() {
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "٠٠١٢٣"]);
+    chr.assert().success().stdout("123\n");
+
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "000"]);
+    chr.assert().success().stdout("0\n");
+
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "०"]);
+    chr.assert().success().stdout("0\n");
+}
+
+#[test]
+fn fails_with_mixed_or_invalid_digits() {
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "४2"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "'४2' mixes digits of different numbering systems, \
+         such as U+096A DEVANAGARI DIGIT FOUR and U+0032 DIGIT TWO",
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "12a"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "'a' U+0061 is not a decimal digit",
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", "1½"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "'½' U+00BD is not a decimal digit",
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--parse-digits", "--string", ""]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "expected at least one decimal digit",
+    ));
+}
+
+#[test]
+fn succeeds_with_bidi_class_filter() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{hex} {bidi_class}",
+        "--bidi-class",
+        "explicit formatting",
+        "--block",
+        "General Punctuation",
+        "--age",
+        "<6.3",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+202A LRE
+        U+202B RLE
+        U+202C PDF
+        U+202D LRO
+        U+202E RLO
+        "
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--set", r"[\p{bc=AN}&&\p{Block=Arabic}]", "--ranges"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+0600..U+0605
+        U+0660..U+0669
+        U+066B..U+066C
+        U+06DD
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_filters_combined_with_name_search() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{name}",
+        "--name",
+        "face",
+        "--category",
+        "Other Symbol",
+        "--age",
+        "13.0..",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        DISGUISED FACE
+        SMILING FACE WITH TEAR
+        "
+    ));
+}
+
+#[test]
+fn fails_with_unknown_category_bidi_class_or_block() {
+    let mut chr = init_command();
+    chr.args(["--category", "Xy"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--category <CATEGORY>': unknown category 'Xy'",
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--bidi-class", "Upside Down"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--bidi-class <BIDI_CLASS>': unknown bidi class 'Upside Down'",
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--block", "Latin Extended-Z"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--block <BLOCK>': unknown block 'Latin Extended-Z'",
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--category", "Lu,Cn"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--category <CATEGORY>': unassigned code points of category Cn \
+         are not stored in the database",
+    ));
+}
+
+#[test]
+fn succeeds_with_unicode_set() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char}",
+        "--set",
+        r"[\p{Lu}&&\p{Block=Greek_and_Coptic}&&[\u0391-\u0395]]",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        Α
+        Β
+        Γ
+        Δ
+        Ε
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_unicode_set_as_ranges() {
+    let mut chr = init_command();
+    chr.args(["--ranges", "--set", r"[a-zà-ÿ-[\p{Sm}] [\p{Latin}&\p{Lo}]]"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+0061..U+007A
+        U+00AA
+        U+00BA
+        U+00E0..U+00F6
+        U+00F8..U+00FF
+        U+01BB
+        U+01C0..U+01C3
+        U+0294
+        U+A78F
+        U+A7F7
+        U+A7FB..U+A7FF
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_unassigned_code_points_in_unicode_set() {
+    let mut chr = init_command();
+    chr.args(["--ranges", "--set", r"[[Ͱ-Ϗ]&\p{Cn}]"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+0378..U+0379
+        U+0380..U+0383
+        U+038B
+        U+038D
+        U+03A2
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_character_names_in_unicode_set() {
+    let mut chr = init_command();
+    chr.args([
+        "--ranges",
+        "--set",
+        r"[\N{latin small letter a}-\N{LATIN SMALL LETTER C}\N{LINE FEED}]",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+000A
+        U+0061..U+0063
+        "
+    ));
+}
+
+#[test]
+fn fails_with_unknown_character_name_in_unicode_set() {
+    let mut chr = init_command();
+    chr.args(["--set", r"[\N{LATIN SMALL LETTER KLINGON}]"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--set <SET>': there is no character named 'LATIN SMALL LETTER KLINGON'",
+    ));
+}
+
+#[test]
+fn fails_with_unknown_unicode_set_property() {
+    let mut chr = init_command();
+    chr.args(["--set", r"[\p{L}-\p{Klingon}]"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--set <SET>': unknown property 'Klingon'",
+    ));
+}
+
+#[test]
+fn succeeds_with_sort_offset_and_limit() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{age} {name}",
+        "--name",
+        "heart",
+        "--category",
+        "So",
+        "--sort",
+        "age:desc",
+        "--offset",
+        "1",
+        "--limit",
+        "3",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        12.0 WHITE HEART
+        12.0 BROWN HEART
+        11.0 SMILING FACE WITH SMILING EYES AND THREE HEARTS
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_sort_offset_and_limit_in_unicode_set() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char}",
+        "--set",
+        "[A-Ea-e]",
+        "--sort",
+        "name:desc",
+        "--offset",
+        "1",
+        "--limit",
+        "3",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        d
+        c
+        b
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_count_option() {
+    let mut chr = init_command();
+    chr.args(["--count", "--name", "heart"]);
+    chr.assert().success().stdout("48\n");
+}
+
+#[test]
+fn succeeds_with_input_order_and_duplicates() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char}",
+        "--input-order",
+        "0x42",
+        "U+0041..U+0043",
+        "A",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        B
+        A
+        B
+        C
+        A
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_decimal_range_of_code_points() {
+    let mut chr = init_command();
+    chr.args(["--template", "{hex}", "128512", "65..66", "128512..128513"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        U+0041
+        U+0042
+        U+1F600
+        U+1F601
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_string_option() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--string", "e\u{301}@"]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains(indoc!(
+            "
+            1.	e\u{301}	U+0065 U+0301
+
+            1.1.	e	U+0065
+            LATIN SMALL LETTER E
+            Basic Latin	Lowercase Letter
+            since 1.1
+
+            1.2.	\u{301}	U+0301
+            COMBINING ACUTE ACCENT
+            Unicode 1.0 name NON-SPACING ACUTE
+            Combining Diacritical Marks	Non-spacing Mark
+            since 1.1
+
+            2.	@	U+0040
+
+            2.1.	@	U+0040
+            COMMERCIAL AT
+            Basic Latin	Other Punctuation
+            since 1.1
+            "
+        )));
+}
+
+#[test]
+fn succeeds_with_codepoint_notations_and_ranges() {
+    let mut chr = init_command();
+    chr.args([
+        "--no-paging",
+        "U+1F36F",
+        "0x21",
+        "\\u{24}",
+        "64",
+        "U+00C4..U+00C5",
+    ]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains(indoc!(
+            "
+            1.	!	U+0021
+            EXCLAMATION MARK
+            Basic Latin	Other Punctuation
+            since 1.1
+
+            2.	$	U+0024
+            DOLLAR SIGN
+            Basic Latin	Currency Sign
+            since 1.1
+
+            3.	@	U+0040
+            COMMERCIAL AT
+            Basic Latin	Other Punctuation
+            since 1.1
+
+            4.	Ä	U+00C4
+            LATIN CAPITAL LETTER A WITH DIAERESIS
+            Unicode 1.0 name LATIN CAPITAL LETTER A DIAERESIS
+            Latin-1 Supplement	Uppercase Letter
+            since 1.1
+
+            5.	Å	U+00C5
+            LATIN CAPITAL LETTER A WITH RING ABOVE
+            Unicode 1.0 name LATIN CAPITAL LETTER A RING
+            Latin-1 Supplement	Uppercase Letter
+            since 1.1
+
+            6.	🍯	U+1F36F
+            HONEY POT
+            Miscellaneous Symbols and Pictographs	Other Symbol
+            since 6.0
+            "
+        )));
+}
+
+#[test]
+fn succeeds_with_json_output_format() {
+    let mut chr = init_command();
+    chr.args(["--format", "json", "ß"]);
+    chr.assert().success().stdout(indoc!(
+        r#"
+        [
+          {
+            "char": "ß",
+            "codepoint": 223,
+            "hex": "U+00DF",
+            "name": "LATIN SMALL LETTER SHARP S",
+            "category": "Ll",
+            "category_description": "Lowercase Letter",
+            "block": "Latin-1 Supplement",
+            "age": "1.1",
+            "aliases": [],
+            "unicode_1_name": null,
+            "iso_comment": null,
+            "combining_class": 0,
+            "decomposition": null,
+            "uppercase": {
+              "full": "SS",
+              "simple": "ß"
+            },
+            "lowercase": null,
+            "titlecase": {
+              "full": "Ss",
+              "simple": "ß"
+            },
+            "case_folding": {
+              "full": "ss",
+              "simple": "ß"
+            },
+            "bidi_class": "L",
+            "bidi_class_description": "Left To Right",
+            "bidi_class_type": "strong",
+            "bidi_mirrored": false,
+            "bidi_mirroring_glyph": null,
+            "bidi_paired_bracket": null,
+            "numeric_type": null,
+            "numeric_value": null
+          }
+        ]
+        "#
+    ));
+}
+
+#[test]
+fn succeeds_with_ndjson_output_format() {
+    let mut chr = init_command();
+    chr.args(["--format", "ndjson", "--name", "honey"]);
+    chr.assert().success().stdout(indoc!(
+        r#"
+        {"char":"🍯","codepoint":127855,"hex":"U+1F36F","name":"HONEY POT","category":"So","category_description":"Other Symbol","block":"Miscellaneous Symbols and Pictographs","age":"6.0","aliases":[],"unicode_1_name":null,"iso_comment":null,"combining_class":0,"decomposition":null,"uppercase":null,"lowercase":null,"titlecase":null,"case_folding":null,"bidi_class":"ON","bidi_class_description":"Other Neutral","bidi_class_type":"neutral","bidi_mirrored":false,"bidi_mirroring_glyph":null,"bidi_paired_bracket":null,"numeric_type":null,"numeric_value":null}
+        {"char":"🐝","codepoint":128029,"hex":"U+1F41D","name":"HONEYBEE","category":"So","category_description":"Other Symbol","block":"Miscellaneous Symbols and Pictographs","age":"6.0","aliases":[],"unicode_1_name":null,"iso_comment":null,"combining_class":0,"decomposition":null,"uppercase":null,"lowercase":null,"titlecase":null,"case_folding":null,"bidi_class":"ON","bidi_class_description":"Other Neutral","bidi_class_type":"neutral","bidi_mirrored":false,"bidi_mirroring_glyph":null,"bidi_paired_bracket":null,"numeric_type":null,"numeric_value":null}
+        "#
+    ));
+}
+
+#[test]
+fn succeeds_with_csv_output_format() {
+    let mut chr = init_command();
+    chr.args(["--format", "csv", "\"", "ß", "é", "Ƣ"]);
+    chr.assert().success().stdout(indoc!(
+        r#"
+        char,codepoint,hex,name,category,category_description,block,age,aliases,unicode_1_name,iso_comment,combining_class,decomposition,uppercase,lowercase,titlecase,case_folding,bidi_class,bidi_class_description,bidi_class_type,bidi_mirrored,bidi_mirroring_glyph,bidi_paired_bracket,numeric_type,numeric_value
+        """",34,U+0022,QUOTATION MARK,Po,Other Punctuation,Basic Latin,1.1,,,,0,,,,,,ON,Other Neutral,neutral,false,,,,
+        ß,223,U+00DF,LATIN SMALL LETTER SHARP S,Ll,Lowercase Letter,Latin-1 Supplement,1.1,,,,0,,SS (simple ß),,Ss (simple ß),ss (simple ß),L,Left To Right,strong,false,,,,
+        é,233,U+00E9,LATIN SMALL LETTER E WITH ACUTE,Ll,Lowercase Letter,Latin-1 Supplement,1.1,,LATIN SMALL LETTER E ACUTE,,0,U+0065 U+0301,É,,É,,L,Left To Right,strong,false,,,,
+        Ƣ,418,U+01A2,LATIN CAPITAL LETTER OI,Lu,Uppercase Letter,Latin Extended-B,1.1,LATIN CAPITAL LETTER GHA (correction),LATIN CAPITAL LETTER O I,,0,,,ƣ,,ƣ,L,Left To Right,strong,false,,,,
+        "#
+    ));
+}
+
+#[test]
+fn succeeds_with_table_output_format() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--format", "table", "A", "🍯"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        No.  Char  Code Point  Name                    Block                                  Category          Age
+        1    A     U+0041      LATIN CAPITAL LETTER A  Basic Latin                            Uppercase Letter  1.1
+        2    🍯    U+1F36F     HONEY POT               Miscellaneous Symbols and Pictographs  Other Symbol      6.0
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_output_template() {
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        r"{char}\t{codepoint:06x}\t{name:lower}",
+        "--name",
+        "honey",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        🍯	01f36f	honey pot
+        🐝	01f41d	honeybee
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_output_template_into_closed_pipe() {
+    assert_quiet_exit_on_closed_pipe(&["--template", "{char}", "--name", "honey"]);
+}
+
+#[test]
+fn succeeds_with_json_output_into_closed_pipe() {
+    assert_quiet_exit_on_closed_pipe(&["--format", "json", "--name", "honey"]);
+}
+
+#[test]
+fn succeeds_with_ndjson_output_into_closed_pipe() {
+    assert_quiet_exit_on_closed_pipe(&["--format", "ndjson", "--name", "honey"]);
+}
+
+#[test]
+fn succeeds_with_unpaged_text_output_into_closed_pipe() {
+    assert_quiet_exit_on_closed_pipe(&["--no-paging", "--name", "honey"]);
+}
+
+#[test]
+fn succeeds_with_ranges_of_unicode_set_into_closed_pipe() {
+    assert_quiet_exit_on_closed_pipe(&["--set", r"[\p{Cn}]", "--ranges"]);
+}
+
+#[test]
+fn succeeds_with_normalization_of_string() {
+    let mut chr = init_command();
+    chr.args([
+        "--no-paging",
+        "--normalize",
+        "nfc",
+        "--string",
+        "Cafe\u{301} \u{1100}\u{1161}",
+    ]);
+    chr.assert().success().stdout(indoc!(
+        "
+        1:4
+        U+0065 LATIN SMALL LETTER E    →  U+00E9 LATIN SMALL LETTER E WITH ACUTE
+        U+0301 COMBINING ACUTE ACCENT
+
+        1:7
+        U+1100 HANGUL CHOSEONG KIYEOK  →  U+AC00 HANGUL SYLLABLE GA
+        U+1161 HANGUL JUNGSEONG A
+        "
+    ));
+
+    let mut chr = init_command();
+    chr.args([
+        "--normalize",
+        "NFKD",
+        "--normalized-only",
+        "--string",
+        "ﬁ Å\u{323}",
+    ]);
+    chr.assert().success().stdout("fi A\u{323}\u{30a}");
+}
+
+#[test]
+fn succeeds_with_case_mappings_in_detailed_view() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--details", "ﬁ"]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains(indoc!(
+            "
+            uppercase → FI U+0046 U+0049 (simple ﬁ U+FB01)
+            titlecase → Fi U+0046 U+0069 (simple ﬁ U+FB01)
+            case folding → fi U+0066 U+0069 (simple ﬁ U+FB01)
+            "
+        )));
+
+    let mut chr = init_command();
+    chr.args([
+        "--template",
+        "{char} {uppercase} {lowercase} {titlecase} {case_folding}",
+        "ß",
+        "ǆ",
+    ]);
+    chr.assert().success().stdout("ß SS ß Ss ss\nǆ Ǆ ǆ ǅ ǆ\n");
+}
+
+#[test]
+fn succeeds_with_case_conversion_of_string() {
+    let mut chr = init_command();
+    chr.args(["--case", "upper", "--string", "Straße"]);
+    chr.assert().success().stdout("STRASSE\n");
+
+    let mut chr = init_command();
+    chr.args(["--case", "title", "--string", "ﬁsh and CHIPS"]);
+    chr.assert().success().stdout("Fish And Chips\n");
+
+    let mut chr = init_command();
+    chr.args(["--case", "lower", "--string", "ΟΔΟΣ ΣΑΣ"]);
+    chr.assert().success().stdout("οδος σας\n");
+
+    let mut chr = init_command();
+    chr.args(["--case", "fold", "--string", "Maße MASSE"]);
+    chr.assert().success().stdout("masse masse\n");
+}
+
+#[test]
+fn succeeds_with_language_specific_case_conversion() {
+    let mut chr = init_command();
+    chr.args([
+        "--case",
+        "upper",
+        "--language",
+        "tr",
+        "--string",
+        "istanbul",
+    ]);
+    chr.assert().success().stdout("İSTANBUL\n");
+
+    let mut chr = init_command();
+    chr.args([
+        "--case",
+        "lower",
+        "--language",
+        "az",
+        "--string",
+        "IİI\u{307}",
+    ]);
+    chr.assert().success().stdout("ıii\n");
+
+    let mut chr = init_command();
+    chr.args([
+        "--case",
+        "lower",
+        "--language",
+        "lt",
+        "--string",
+        "ÌI\u{301}",
+    ]);
+    chr.assert()
+        .success()
+        .stdout("i\u{307}\u{300}i\u{307}\u{301}\n");
+
+    let mut chr = init_command();
+    chr.args([
+        "--case",
+        "upper",
+        "--language",
+        "lt",
+        "--string",
+        "i\u{307}",
+    ]);
+    chr.assert().success().stdout("I\n");
+}
+
+#[test]
+fn succeeds_with_normalization_check_of_normalized_text() {
+    let mut chr = init_command();
+    chr.args(["--normalize", "nfc", "--check", "--string", "Café"]);
+    chr.assert().success().stdout("Text is already in NFC\n");
+}
+
+#[test]
+fn fails_with_unknown_template_field() {
+    let mut chr = init_command();
+    chr.args(["--template", "{char} {glyph}", "Ä"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '--template <TEMPLATE>': unknown field 'glyph'",
+    ));
+}
+
+#[test]
+fn fails_with_unknown_case_or_language() {
+    let mut chr = init_command();
+    chr.args(["--case", "camel", "--string", "text"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "unknown case 'camel', expected upper, lower, title or fold",
+    ));
+
+    let mut chr = init_command();
+    chr.args(["--case", "upper", "--language", "turkish", "--string", "i"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "'turkish' is not a language code such as tr, az or lt",
+    ));
+}
+
+#[test]
+fn succeeds_with_bidi_resolution_of_string() {
+    let mut chr = init_command();
+    chr.args(["--no-paging", "--bidi", "rtl", "--string", "אב (c)"]);
+    chr.assert().success().stdout(indoc!(
+        "
+        Paragraph 1 at level 1 (right-to-left)
+
+        Levels
+        1  U+05D0  R       level 1  HEBREW LETTER ALEF
+        2  U+05D1  R       level 1  HEBREW LETTER BET
+        3  U+0020  WS → R  level 1  SPACE
+        4  U+0028  ON → R  level 1  LEFT PARENTHESIS
+        5  U+0063  L       level 2  LATIN SMALL LETTER C
+        6  U+0029  ON → R  level 1  RIGHT PARENTHESIS
+
+        Runs
+        1–4  level 1  right-to-left  אב (
+        5    level 2  left-to-right  c
+        6    level 1  right-to-left  )
+
+        Visual order
+        6 5 4 3 2 1
+        (c) בא
+        "
+    ));
+}
+
+#[test]
+fn succeeds_with_bidi_isolates_and_overrides() {
+    let mut chr = init_command();
+    chr.args([
+        "--bidi",
+        "auto",
+        "--string",
+        "a \u{202E}bc\u{202C} \u{2067}d\u{2069}",
+    ]);
+    chr.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Paragraph 1 at level 0 (left-to-right)",
+        ))
+        .stdout(predicate::str::contains(
+            "3  U+202E  RLO      removed  RIGHT-TO-LEFT OVERRIDE  starts right-to-left override",
+        ))
+        .stdout(predicate::str::contains(
+            "4  U+0062  L → R    level 1  LATIN SMALL LETTER B  overridden to R",
+        ))
+        .stdout(predicate::str::contains(
+            "6  U+202C  PDF      removed  POP DIRECTIONAL FORMATTING  ends override",
+        ))
+        .stdout(predicate::str::contains(
+            "8  U+2067  RLI → L  level 0  RIGHT-TO-LEFT ISOLATE  starts right-to-left isolate",
+        ))
+        .stdout(predicate::str::contains(
+            "9  U+0064  L        level 2  LATIN SMALL LETTER D",
+        ))
+        .stdout(predicate::str::contains(
+            "10  U+2069  PDI → L  level 0  POP DIRECTIONAL ISOLATE  ends isolate",
+        ))
+        .stdout(predicate::str::contains("1 2 5 4 7 8 9 10"));
+}
+
+#[test]
+fn succeeds_with_bidi_brackets_paired_with_canonical_equivalents() {
+    let lines = resolve_bidi("rtl", "a\u{2329}b\u{3009}\u{05D0}");
+    assert_eq!(levels_of(&lines), "2 2 2 2 1");
+
+    let lines = resolve_bidi("rtl", "a\u{3008}b\u{232A}\u{05D0}");
+    assert_eq!(levels_of(&lines), "2 2 2 2 1");
+
+    let lines = resolve_bidi("rtl", "a\u{2329}b\u{3008}\u{05D0}");
+    assert_eq!(levels_of(&lines), "2 2 2 1 1");
+}
+
+#[test]
+fn succeeds_with_bidi_embeddings_beyond_maximum_depth() {
+    let text = "\u{202B}".repeat(64) + "x\u{202C}\u{202C}y";
+    let lines = resolve_bidi("ltr", &text);
+
+    assert_eq!(levels_of(&lines), "x ".repeat(64) + "126 x x 124");
+    assert!(lines[62].ends_with("starts right-to-left embedding"));
+    assert!(lines[63].ends_with("ignored beyond maximum depth"));
+    assert!(lines[65].ends_with("ignored beyond maximum depth"));
+    assert!(lines[66].ends_with("ends embedding"));
+}
+
+#[test]
+fn succeeds_with_bidi_isolates_beyond_maximum_depth() {
+    let text = "\u{202B}".repeat(63) + "\u{2067}x\u{2069}y\u{202C}z";
+    let lines = resolve_bidi("ltr", &text);
+
+    assert_eq!(levels_of(&lines), "x ".repeat(63) + "125 126 126 126 x 124");
+    assert!(lines[63].ends_with("ignored beyond maximum depth"));
+    assert!(lines[65].ends_with("ignored beyond maximum depth"));
+    assert!(lines[67].ends_with("ends embedding"));
+}
+
+#[test]
+fn succeeds_with_unmatched_bidi_pdf_and_pdi() {
+    let lines = resolve_bidi("ltr", "a\u{202C}\u{05D0}");
+
+    assert_eq!(levels_of(&lines), "0 x 1");
+    assert!(lines[1].ends_with("ignored without initiator"));
+
+    let lines = resolve_bidi("ltr", "\u{202B}a\u{2069}b\u{202C}c");
+
+    assert_eq!(levels_of(&lines), "x 2 2 2 x 0");
+    assert!(lines[2].ends_with("ignored without initiator"));
+    assert!(lines[4].ends_with("ends embedding"));
+}
+
+#[test]
+fn succeeds_with_bidi_direction_of_first_strong_isolate() {
+    let lines = resolve_bidi("ltr", "a\u{2068}\u{05D0}b\u{2069}c");
+
+    assert_eq!(levels_of(&lines), "0 0 1 2 0 0");
+    assert!(lines[1].ends_with("starts right-to-left isolate"));
+
+    let lines = resolve_bidi("rtl", "\u{2068}\u{2066}\u{05D0}\u{2069}b\u{2069}\u{05D1}");
+
+    assert_eq!(levels_of(&lines), "1 2 5 2 2 1 1");
+    assert!(lines[0].ends_with("starts left-to-right isolate"));
+
+    let lines = resolve_bidi("ltr", "a\u{2068}1\u{05D0}b");
+
+    assert_eq!(levels_of(&lines), "0 0 2 1 2");
+}
+
+#[test]
+fn succeeds_with_bidi_paragraph_level_from_first_strong_character() {
+    let mut chr = init_command();
+    chr.args([
+        "--no-paging",
+        "--bidi",
+        "auto",
+        "--string",
+        "\u{202A} \u{05D0}a",
+    ]);
+    chr.assert().success().stdout(predicate::str::starts_with(
+        "Paragraph 1 at level 1 (right-to-left)",
+    ));
+
+    let lines = resolve_bidi("auto", "\u{202A} \u{05D0}a");
+    assert_eq!(levels_of(&lines), "x 2 3 2");
+}
+
+#[test]
+fn fails_with_unknown_bidi_direction() {
+    let mut chr = init_command();
+    chr.args(["--bidi", "up", "--string", "text"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "unknown direction 'up', expected ltr, rtl or auto",
+    ));
+}
+
+#[test]
+fn fails_partially_with_missing_characters() {
+    let mut chr = init_command();
+    chr.args(["--template", "{char}", "A", "U+0378", "U+FDD0", "U+1FAE0"]);
+    chr.assert()
+        .code(2)
+        .stdout("A\n")
+        .stderr(indoc!(
+            "
+            U+0378 <reserved-0378> is not in the database: unassigned code point
+            U+FDD0 <noncharacter-FDD0> is not in the database: noncharacter
+            U+1FAE0 <reserved-1FAE0> is not in the database: assigned in Unicode 14.0, newer than the database's Unicode 13.0.0
+            "
+        ));
+}
+
+#[test]
+fn fails_completely_with_only_missing_characters() {
+    let mut chr = init_command();
+    chr.args(["--template", "{char}", "U+FFFE"]);
+    chr.assert()
+        .code(3)
+        .stdout("")
+        .stderr("U+FFFE <noncharacter-FFFE> is not in the database: noncharacter\n");
+}
+
+#[test]
+fn fails_completely_with_only_missing_characters_and_empty_output() {
+    for format in ["text", "table", "json", "csv"] {
+        let mut chr = init_command();
+        chr.args(["--no-paging", "--format", format, "U+FFFE", "U+0378"]);
+        chr.assert().code(3).stdout("");
+    }
+}
+
+#[test]
+fn fails_with_normalization_check_of_unnormalized_file() {
+    let file_path = std::env::temp_dir().join("chr_normalization_check.txt");
+    std::fs::write(&file_path, "e\u{301}\nx\u{307}\u{323}\n").unwrap();
+
+    let mut chr = init_command();
+    chr.args(["--normalize", "nfc", "--check", "--file"]);
+    chr.arg(&file_path);
+    chr.assert().code(4).stdout(format!(
+        "{0}:1:1: U+0065 U+0301 is not in NFC, expected U+00E9\n\
+         {0}:2:1: U+0078 U+0307 U+0323 is not in NFC, expected U+1E8B U+0323\n",
+        file_path.display()
+    ));
+}
+
+#[test]
+fn fails_with_codepoint_beyond_unicode_range() {
+    let mut chr = init_command();
+    chr.args(["U+110000"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "'U+110000' is beyond the last Unicode code point U+10FFFF",
+    ));
+}
+
 #[test]
 fn fails_with_string_instead_of_chars() {
     let mut chr = init_command();
-    chr.args(&["Ä@"]);
+    chr.args(["Ä@"]);
+    chr.assert().failure().stderr(predicate::str::contains(
+        "Invalid value for '<CHARS>...': 'Ä@' is neither a single character nor a code point",
+    ));
+}
+
+#[test]
+fn fails_with_letters_as_range_bounds() {
+    let mut chr = init_command();
+    chr.args(["a..b"]);
     chr.assert().failure().stderr(predicate::str::contains(
-        "Invalid value for '<CHARS>...': too many characters in string",
+        "Invalid value for '<CHARS>...': 'a..b' is not a valid range of code points",
     ));
 }
 
 #[test]
 fn fails_with_both_character_and_name_search_option() {
     let mut chr = init_command();
-    chr.args(&["Ä@", "--name", "honey"]);
+    chr.args(["Ä@", "--name", "honey"]);
     chr.assert().failure().stderr(predicate::str::contains(
         "error: The argument '--name <NAME>' cannot be used with 'chars'",
     ));
 }
 
+#[test]
+fn succeeds_with_version_flag() {
+    let mut chr = init_command();
+    chr.args(["--version"]);
+    chr.assert().success().stdout(
+        predicate::str::is_match(r"^chr \d+\.\d+\.\d+ \(Unicode \d+\.\d+\.\d+\)\n$").unwrap(),
+    );
+}
+
+// The home directory is only taken from HOME on Unix.
+#[cfg(unix)]
+#[test]
+fn succeeds_with_removing_databases_of_other_builds() {
+    let home_directory = std::env::temp_dir().join("chr_integration_test_home");
+    let database_directory = home_directory.join(".chr");
+    let outdated_database = database_directory.join("chr_1_0_0_unicode_12_1_0_0123456789abcdef.db");
+    let unrelated_file = database_directory.join("notes.txt");
+
+    let _ = std::fs::remove_dir_all(&home_directory);
+    std::fs::create_dir_all(&database_directory).unwrap();
+    std::fs::write(&outdated_database, "").unwrap();
+    std::fs::write(&unrelated_file, "").unwrap();
+
+    let mut chr = init_command();
+    chr.env("HOME", &home_directory).args(["--no-paging", "A"]);
+    chr.assert().success();
+
+    assert!(!outdated_database.exists());
+    assert!(unrelated_file.exists());
+
+    std::fs::remove_dir_all(&home_directory).unwrap();
+}
+
 fn init_command() -> Command {
     Command::cargo_bin("chr").unwrap()
 }
+
+/// Returns the lines of the levels section that the bidi resolution of `text` prints.
+fn resolve_bidi(direction: &str, text: &str) -> Vec<String> {
+    let output = init_command()
+        .args(["--no-paging", "--bidi", direction, "--string", text])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .skip_while(|line| *line != "Levels")
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Returns the levels of the given lines in the notation of BidiCharacterTest.txt,
+/// where `x` stands for a character removed by rule X9.
+fn levels_of(lines: &[String]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            line.split("  ")
+                .map(str::trim)
+                .find_map(|column| match column {
+                    "removed" => Some("x"),
+                    _ => column.strip_prefix("level "),
+                })
+                .unwrap()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn assert_quiet_exit_on_closed_pipe(args: &[&str]) {
+    let mut child = init_command()
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    drop(child.stdout.take());
+
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"));
+}